@@ -0,0 +1,312 @@
+use crate::{default_input, read_input, Day};
+use common::{parse_token, Error, ParseError};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::hint::black_box;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+const HEADER: &str = "run,day,stage,nanoseconds";
+
+// Stages faster than this are never reported as regressions, their timings
+// being mostly noise.
+const NOISE_FLOOR: Duration = Duration::from_micros(50);
+
+// Fewest iterations for the fastest run to be steady enough to flag regressions.
+const MIN_FLAGGING_ITERATIONS: usize = 5;
+
+// A stage is repeated until it has run `iterations` times or for this long, so
+// that the slowest solvers are only run a few times.
+const TIME_BUDGET: Duration = Duration::from_secs(1);
+
+pub struct Options {
+    iterations: usize,
+    // slowdown, in percent, above which a stage is reported as a regression
+    threshold: f64,
+    output: String,
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Option<Self> {
+        let mut options = Options {
+            iterations: 10,
+            threshold: 10.0,
+            output: "bench.csv".to_string(),
+        };
+
+        for pair in args.chunks(2) {
+            let [flag, value] = pair else {
+                return None;
+            };
+            match flag.as_str() {
+                "--iterations" => options.iterations = value.parse().ok().filter(|&n| n > 0)?,
+                "--threshold" => options.threshold = value.parse().ok().filter(|&t| t >= 0.0)?,
+                "--output" => options.output = value.clone(),
+                _ => return None,
+            }
+        }
+
+        Some(options)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Stage {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl Stage {
+    const ALL: [Stage; 3] = [Stage::Parse, Stage::Part1, Stage::Part2];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Stage::Parse => "parse",
+            Stage::Part1 => "part1",
+            Stage::Part2 => "part2",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Stage::ALL.into_iter().find(|stage| stage.name() == name)
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Stage::Parse => "parse",
+            Stage::Part1 => "part 1",
+            Stage::Part2 => "part 2",
+        }
+    }
+}
+
+type Timings = HashMap<(usize, Stage), Duration>;
+
+// Fastest of several runs of `f`.
+fn fastest<T, E>(iterations: usize, f: impl Fn() -> Result<T, E>) -> Result<Duration, E> {
+    let mut best = Duration::MAX;
+    let start = Instant::now();
+
+    for _ in 0..iterations {
+        let run = Instant::now();
+        black_box(f()?);
+        best = best.min(run.elapsed());
+
+        if start.elapsed() > TIME_BUDGET {
+            break;
+        }
+    }
+
+    Ok(best)
+}
+
+/// Fastest parse of `input`, along with what it parsed into for the parts.
+pub fn time_parse<'a, P>(
+    input: &'a str,
+    iterations: usize,
+    parse: impl Fn(&'a str) -> Result<P, ParseError>,
+) -> Result<(P, Duration), Error> {
+    let elapsed = fastest(iterations, || parse(input))?;
+    Ok((parse(input)?, elapsed))
+}
+
+/// Fastest solve of a part from its parsed input, the parsing left out.
+pub fn time_part<P, T>(
+    parsed: &P,
+    iterations: usize,
+    solve: impl Fn(&P) -> Result<T, Error>,
+) -> Result<Duration, Error> {
+    fastest(iterations, || solve(parsed))
+}
+
+// A timing of the history file, the runs being numbered from 1.
+#[derive(Debug, PartialEq, Eq)]
+struct Row {
+    run: usize,
+    day: usize,
+    stage: Stage,
+    elapsed: Duration,
+}
+
+fn parse_history(input: &str) -> Result<Vec<Row>, ParseError> {
+    input
+        .lines()
+        .skip_while(|&line| line == HEADER)
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let [run, day, stage, nanoseconds] = fields[..] else {
+                return Err(ParseError::at(
+                    input,
+                    line,
+                    "\"<run>,<day>,<stage>,<nanoseconds>\"",
+                ));
+            };
+            let run: usize = parse_token(input, run, "a run number")?;
+            let day: usize = parse_token(input, day, "a day")?;
+            let stage = Stage::from_name(stage)
+                .ok_or_else(|| ParseError::at(input, stage, "parse, part1 or part2"))?;
+            let nanoseconds: u64 = parse_token(input, nanoseconds, "a duration in nanoseconds")?;
+
+            Ok(Row {
+                run,
+                day,
+                stage,
+                elapsed: Duration::from_nanos(nanoseconds),
+            })
+        })
+        .collect()
+}
+
+// The last run of `history`, and the timing of each stage in the latest run
+// that timed it, days skipped by a run keeping their earlier timings.
+fn latest_timings(history: &[Row]) -> (usize, Timings) {
+    let mut runs: HashMap<(usize, Stage), usize> = HashMap::new();
+    let mut timings = Timings::new();
+    for row in history {
+        let key = (row.day, row.stage);
+        if runs.get(&key).is_none_or(|&run| run <= row.run) {
+            runs.insert(key, row.run);
+            timings.insert(key, row.elapsed);
+        }
+    }
+
+    let last_run = history.iter().map(|row| row.run).max().unwrap_or(0);
+    (last_run, timings)
+}
+
+fn previous_timings(path: &str) -> Result<(usize, Timings), String> {
+    match fs::read_to_string(path) {
+        Ok(input) => parse_history(&input)
+            .map(|history| latest_timings(&history))
+            .map_err(|e| format!("{}: {}", path, e)),
+        Err(_) => Ok((0, Timings::new())),
+    }
+}
+
+// Appends the timings of a run to the history file, creating it if needed.
+fn append_timings(
+    path: &str,
+    run: usize,
+    timings: &[(usize, Stage, Duration)],
+) -> Result<(), String> {
+    let mut csv = String::new();
+    if fs::metadata(path).map_or(true, |metadata| metadata.len() == 0) {
+        csv += &format!("{}\n", HEADER);
+    }
+    for (day, stage, elapsed) in timings {
+        csv += &format!("{},{},{},{}\n", run, day, stage.name(), elapsed.as_nanos());
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(csv.as_bytes()))
+        .map_err(|e| format!("cannot write {}: {}", path, e))
+}
+
+pub fn run(days: &[Day], options: &Options) -> Result<(), String> {
+    let (last_run, previous) = previous_timings(&options.output)?;
+    let mut timings = Vec::new();
+    let mut regressions = 0;
+    let flagging = options.iterations >= MIN_FLAGGING_ITERATIONS;
+
+    for (i, solvers) in days.iter().enumerate() {
+        let day = i + 1;
+        let path = default_input(day);
+        let input = match read_input(&path) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("day {}: {}", day, e);
+                continue;
+            }
+        };
+        let day_timings = match (solvers.bench)(&input, options.iterations) {
+            Ok(day_timings) => day_timings,
+            Err(e) => {
+                eprintln!("day {}: {}: {}", day, path, e);
+                continue;
+            }
+        };
+
+        println!("day {}", day);
+        for (stage, elapsed) in day_timings {
+            print!("  {}: {:.2?}", stage.label(), elapsed);
+            if let Some(&before) = previous.get(&(day, stage)) {
+                let change = (elapsed.as_secs_f64() / before.as_secs_f64() - 1.0) * 100.0;
+                print!(" (was {:.2?}, {:+.1}%)", before, change);
+                if flagging && change > options.threshold && elapsed >= NOISE_FLOOR {
+                    print!(" regression");
+                    regressions += 1;
+                }
+            }
+            println!();
+            timings.push((day, stage, elapsed));
+        }
+    }
+
+    if !flagging {
+        println!(
+            "regressions aren't flagged below {} iterations",
+            MIN_FLAGGING_ITERATIONS
+        );
+    } else if regressions > 0 {
+        println!(
+            "{} stage(s) more than {}% slower than the previous run",
+            regressions, options.threshold
+        );
+    }
+
+    append_timings(&options.output, last_run + 1, &timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_file() {
+        let history =
+            parse_history("run,day,stage,nanoseconds\n1,1,part1,1500\n1,2,parse,20\n").unwrap();
+        assert_eq!(
+            history[1],
+            Row {
+                run: 1,
+                day: 2,
+                stage: Stage::Parse,
+                elapsed: Duration::from_nanos(20),
+            }
+        );
+        assert_eq!(
+            parse_history("1,3,part3,10\n").err(),
+            Some(ParseError::new(1, 5, "part3", "parse, part1 or part2"))
+        );
+        assert_eq!(
+            parse_history("3,part1,10\n").err(),
+            Some(ParseError::new(
+                1,
+                1,
+                "3,part1,10",
+                "\"<run>,<day>,<stage>,<nanoseconds>\""
+            ))
+        );
+    }
+
+    #[test]
+    fn latest_runs() {
+        // day 2 was skipped by the second run
+        let history = parse_history("1,1,part1,15\n1,2,parse,10\n2,1,part1,12\n").unwrap();
+        let (last_run, timings) = latest_timings(&history);
+        assert_eq!(last_run, 2);
+        assert_eq!(
+            timings.get(&(1, Stage::Part1)),
+            Some(&Duration::from_nanos(12))
+        );
+        assert_eq!(
+            timings.get(&(2, Stage::Parse)),
+            Some(&Duration::from_nanos(10))
+        );
+        assert_eq!(latest_timings(&[]), (0, Timings::new()));
+    }
+}
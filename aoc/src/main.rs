@@ -0,0 +1,450 @@
+mod bench;
+
+use bench::Stage;
+use common::Error;
+use std::env;
+use std::fs;
+use std::process;
+use std::time::{Duration, Instant};
+
+type Solver = Box<dyn Fn(&str) -> Result<String, Error>>;
+type Bencher = fn(&str, usize) -> Result<Vec<(Stage, Duration)>, Error>;
+
+struct Day {
+    part1: Solver,
+    part2: Option<Solver>,
+    details: Option<Solver>,
+    // times the parsing and each part on their own
+    bench: Bencher,
+}
+
+macro_rules! day {
+    ($day:ident) => {
+        Day {
+            part1: Box::new(|input| $day::part1(input).map(|answer| answer.to_string())),
+            part2: Some(Box::new(|input| {
+                $day::part2(input).map(|answer| answer.to_string())
+            })),
+            details: None,
+            bench: |input, iterations| {
+                let (parsed, parse) = bench::time_parse(input, iterations, $day::parse_input)?;
+                Ok(vec![
+                    (Stage::Parse, parse),
+                    (
+                        Stage::Part1,
+                        bench::time_part(&parsed, iterations, $day::solve_part1)?,
+                    ),
+                    (
+                        Stage::Part2,
+                        bench::time_part(&parsed, iterations, $day::solve_part2)?,
+                    ),
+                ])
+            },
+        }
+    };
+    ($day:ident, details) => {
+        Day {
+            details: Some(Box::new($day::details)),
+            ..day!($day)
+        }
+    };
+}
+
+fn days() -> Vec<Day> {
+    vec![
+        day!(day01),
+        day!(day02),
+        day!(day03),
+        day!(day04),
+        day!(day05),
+        day!(day06),
+        day!(day07),
+        day!(day08),
+        day!(day09),
+        day!(day10),
+        day!(day11),
+        day!(day12),
+        day!(day13),
+        day!(day14),
+        day!(day15),
+        day!(day16),
+        day!(day17),
+        day!(day18),
+        day!(day19),
+        day!(day20),
+        day!(day21),
+        day!(day22),
+        day!(day23),
+        day!(day24, details),
+        Day {
+            part1: Box::new(|input| day25::part1(input).map(|answer| answer.to_string())),
+            part2: None,
+            details: Some(Box::new(day25::details)),
+            bench: |input, iterations| {
+                let (parsed, parse) = bench::time_parse(input, iterations, day25::parse_input)?;
+                Ok(vec![
+                    (Stage::Parse, parse),
+                    (
+                        Stage::Part1,
+                        bench::time_part(&parsed, iterations, day25::solve_part1)?,
+                    ),
+                ])
+            },
+        },
+    ]
+}
+
+fn usage() -> ! {
+    eprintln!("usage: aoc run <day> [input]");
+    eprintln!("       aoc run 1 [input] --vocabulary FILE");
+    eprintln!("       aoc run --all");
+    eprintln!("       aoc bench [--iterations N] [--threshold PERCENT] [--output FILE]");
+    eprintln!("       aoc bag <cubes> [input]");
+    eprintln!("       aoc cards [input]");
+    eprintln!("       aoc camel [--rules FILE] [input]");
+    eprintln!("       aoc walk [--from NODE] [--to SUFFIX] [input]");
+    eprintln!("       aoc extrapolate [--steps N] [input]");
+    eprintln!("       aoc pipes [--validate] [input]");
+    eprintln!("       aoc galaxies [--rows N] [--columns N] [input]");
+    eprintln!("       aoc almanac [--target CATEGORY] [--window START-END] [input]");
+    eprintln!("       aoc schematic [--rule NAME:SYMBOLS:ARITY]... [input]");
+    eprintln!("       aoc dot [input]");
+    process::exit(2);
+}
+
+fn default_input(day: usize) -> String {
+    format!("day{:02}/input", day)
+}
+
+fn read_input(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))
+}
+
+fn timed(solver: &Solver, input: &str) -> Result<(String, Duration), Error> {
+    let start = Instant::now();
+    let answer = solver(input)?;
+    Ok((answer, start.elapsed()))
+}
+
+fn run_day(day: usize, solvers: &Day, input: &str) -> Result<Duration, Error> {
+    println!("day {}", day);
+
+    let (answer, mut total) = timed(&solvers.part1, input)?;
+    println!("  part 1: {} ({:.2?})", answer, total);
+
+    if let Some(part2) = &solvers.part2 {
+        let (answer, elapsed) = timed(part2, input)?;
+        println!("  part 2: {} ({:.2?})", answer, elapsed);
+        total += elapsed;
+    }
+
+    if let Some(details) = &solvers.details {
+        println!("  {}", details(input)?);
+    }
+
+    Ok(total)
+}
+
+// Day 1 with its spelled-out digits read from a vocabulary file.
+fn day01_with_vocabulary(path: &str) -> Result<Day, String> {
+    let vocabulary: day01::Vocabulary = read_input(path)?
+        .parse()
+        .map_err(|e| format!("{}: {}", path, e))?;
+
+    Ok(Day {
+        part2: Some(Box::new(move |input| {
+            day01::part2_with_vocabulary(input, &vocabulary).map(|answer| answer.to_string())
+        })),
+        ..days().remove(0)
+    })
+}
+
+// Day 3's annotated schematic, gears being the default rule.
+fn schematic(args: &[String]) -> Result<(), String> {
+    let mut rules = Vec::new();
+    let mut path = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--rule" {
+            let rule = args.next().unwrap_or_else(|| usage());
+            rules.push(
+                rule.parse::<day03::Rule>()
+                    .map_err(|e| format!("rule: {}", e))?,
+            );
+        } else if path.is_none() {
+            path = Some(arg.clone());
+        } else {
+            usage();
+        }
+    }
+    if rules.is_empty() {
+        rules.push("gear:*:2".parse().unwrap());
+    }
+
+    let path = path.unwrap_or(default_input(3));
+    let input = read_input(&path)?;
+    let report = day03::analyze(&input, &rules).map_err(|e| format!("{}: {}", path, e))?;
+    print!("{}", report);
+
+    Ok(())
+}
+
+// Day 5's map from seeds to a category, or the seeds landing in a window of it.
+fn almanac(args: &[String]) -> Result<(), String> {
+    let mut target = "location".to_string();
+    let mut window = None;
+    let mut path = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => target = args.next().unwrap_or_else(|| usage()).clone(),
+            "--window" => {
+                let range = args.next().unwrap_or_else(|| usage());
+                window = range
+                    .split_once('-')
+                    .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+                    .filter(|(start, end)| start <= end);
+                if window.is_none() {
+                    return Err(format!("invalid window: {}", range));
+                }
+            }
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => usage(),
+        }
+    }
+
+    let path = path.unwrap_or(default_input(5));
+    let input = read_input(&path)?;
+    let report = match window {
+        Some((start, end)) => day05::seeds_for(&input, &target, start, end),
+        None => day05::composed_map(&input, &target),
+    };
+    print!("{}", report.map_err(|e| format!("{}: {}", path, e))?);
+
+    Ok(())
+}
+
+// Day 7's winnings under each rule set, the rules of both parts by default.
+fn camel(args: &[String]) -> Result<(), String> {
+    let (rule_sets, args) = match args {
+        [flag, path, args @ ..] if flag == "--rules" => {
+            let rule_sets = read_input(path)?
+                .parse()
+                .map_err(|e| format!("{}: {}", path, e))?;
+            (rule_sets, args)
+        }
+        _ => (day07::RuleSets::default(), args),
+    };
+    let path = match args {
+        [] => default_input(7),
+        [path] => path.clone(),
+        _ => usage(),
+    };
+
+    let input = read_input(&path)?;
+    let winnings = day07::winnings(&input, &rule_sets).map_err(|e| format!("{}: {}", path, e))?;
+    print!("{}", winnings);
+
+    Ok(())
+}
+
+// Day 8's walk between two nodes, step by step.
+fn walk(args: &[String]) -> Result<(), String> {
+    let mut from = "AAA".to_string();
+    let mut to = "ZZZ".to_string();
+    let mut path = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = args.next().unwrap_or_else(|| usage()).clone(),
+            "--to" => to = args.next().unwrap_or_else(|| usage()).clone(),
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => usage(),
+        }
+    }
+
+    let path = path.unwrap_or(default_input(8));
+    let input = read_input(&path)?;
+    let trace = day08::trace(&input, &from, &to).map_err(|e| format!("{}: {}", path, e))?;
+    print!("{}", trace);
+
+    Ok(())
+}
+
+// Day 9's sequences extrapolated any number of steps, backwards if negative.
+fn extrapolate(args: &[String]) -> Result<(), String> {
+    let (steps, args) = match args {
+        [flag, steps, args @ ..] if flag == "--steps" => {
+            let steps = steps
+                .parse()
+                .map_err(|_| format!("invalid steps: {}", steps))?;
+            (steps, args)
+        }
+        _ => (1, args),
+    };
+    let path = match args {
+        [] => default_input(9),
+        [path] => path.clone(),
+        _ => usage(),
+    };
+
+    let input = read_input(&path)?;
+    let report = day09::extrapolate(&input, steps).map_err(|e| format!("{}: {}", path, e))?;
+    print!("{}", report);
+
+    Ok(())
+}
+
+// Day 11's galaxy distances with rows and columns expanded independently.
+fn galaxies(args: &[String]) -> Result<(), String> {
+    let (mut rows, mut columns) = (2, 2);
+    let mut args = args;
+    while let [flag, factor, rest @ ..] = args {
+        let expansion = match flag.as_str() {
+            "--rows" => &mut rows,
+            "--columns" => &mut columns,
+            _ => break,
+        };
+        *expansion = factor
+            .parse()
+            .map_err(|_| format!("invalid expansion: {}", factor))?;
+        args = rest;
+    }
+    let path = match args {
+        [] => default_input(11),
+        [path] => path.clone(),
+        _ => usage(),
+    };
+
+    let input = read_input(&path)?;
+    let sum = day11::distances(&input, rows, columns).map_err(|e| format!("{}: {}", path, e))?;
+    println!("{}", sum);
+
+    Ok(())
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let mut days = days();
+    let mut args = args.to_vec();
+
+    // the flag may come anywhere, the day being what's left first
+    if let Some(i) = args.iter().position(|arg| arg == "--vocabulary") {
+        let Some(path) = args.get(i + 1).cloned() else {
+            usage();
+        };
+        args.drain(i..i + 2);
+        if args.first().is_none_or(|day| day != "1") {
+            return Err("--vocabulary only applies to day 1".to_string());
+        }
+        days[0] = day01_with_vocabulary(&path)?;
+    }
+
+    run_with(&days, &args)
+}
+
+fn run_with(days: &[Day], args: &[String]) -> Result<(), String> {
+    match args {
+        [all] if all == "--all" => {
+            let mut total = Duration::ZERO;
+            for (i, solvers) in days.iter().enumerate() {
+                let day = i + 1;
+                let path = default_input(day);
+                match read_input(&path) {
+                    Ok(input) => match run_day(day, solvers, &input) {
+                        Ok(elapsed) => total += elapsed,
+                        Err(e) => eprintln!("day {}: {}: {}", day, path, e),
+                    },
+                    Err(e) => eprintln!("day {}: {}", day, e),
+                }
+            }
+            println!("total: {:.2?}", total);
+        }
+        [day] | [day, _] => {
+            let day: usize = day
+                .parse()
+                .ok()
+                .filter(|day| (1..=days.len()).contains(day))
+                .ok_or(format!("invalid day: {}", day))?;
+            let path = args.get(1).cloned().unwrap_or(default_input(day));
+            let input = read_input(&path)?;
+            run_day(day, &days[day - 1], &input).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        _ => usage(),
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.split_first() {
+        Some((command, args)) if command == "run" => run(args),
+        Some((command, args)) if command == "bench" => {
+            let options = bench::Options::parse(args).unwrap_or_else(|| usage());
+            bench::run(&days(), &options)
+        }
+        Some((command, args)) if command == "schematic" => schematic(args),
+        Some((command, args)) if command == "almanac" => almanac(args),
+        Some((command, args)) if command == "camel" => camel(args),
+        Some((command, args)) if command == "walk" => walk(args),
+        Some((command, args)) if command == "extrapolate" => extrapolate(args),
+        Some((command, args)) if command == "galaxies" => galaxies(args),
+        Some((command, args)) if command == "bag" && (1..=2).contains(&args.len()) => {
+            let path = args.get(1).cloned().unwrap_or(default_input(2));
+            args[0]
+                .parse::<day02::CubeSet>()
+                .map_err(|e| format!("bag: {}", e))
+                .and_then(|bag| {
+                    let input = read_input(&path)?;
+                    let report =
+                        day02::query(&input, &bag).map_err(|e| format!("{}: {}", path, e))?;
+                    print!("{}", report);
+                    Ok(())
+                })
+        }
+        Some((command, args)) if command == "cards" && args.len() <= 1 => {
+            let path = args.first().cloned().unwrap_or(default_input(4));
+            read_input(&path).and_then(|input| {
+                let trace = day04::trace(&input).map_err(|e| format!("{}: {}", path, e))?;
+                print!("{}", trace);
+                Ok(())
+            })
+        }
+        Some((command, args)) if command == "pipes" && args.len() <= 2 => {
+            let validating = args.first().is_some_and(|arg| arg == "--validate");
+            let args = if validating { &args[1..] } else { args };
+            if args.len() > 1 {
+                usage();
+            }
+            let path = args.first().cloned().unwrap_or(default_input(10));
+            read_input(&path).and_then(|input| {
+                let report = if validating {
+                    day10::validate(&input)
+                } else {
+                    day10::render(&input)
+                };
+                let output = report.map_err(|e| format!("{}: {}", path, e))?;
+                print!("{}", output);
+                Ok(())
+            })
+        }
+        Some((command, args)) if command == "dot" && args.len() <= 1 => {
+            let path = args.first().cloned().unwrap_or(default_input(25));
+            read_input(&path).and_then(|input| {
+                let dot = day25::dot(&input).map_err(|e| format!("{}: {}", path, e))?;
+                print!("{}", dot);
+                Ok(())
+            })
+        }
+        _ => usage(),
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
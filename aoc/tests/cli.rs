@@ -0,0 +1,39 @@
+use std::process::Command;
+
+// The answer lines printed by `aoc` run with `args`, timings left out.
+fn answers(args: &[&str]) -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_aoc"))
+        .current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/.."))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| line.split(" (").next().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn vocabulary_before_day() {
+    let input = std::env::temp_dir().join("aoc_cli_vocabulary_input");
+    std::fs::write(&input, "1zwei\n").unwrap();
+    let input = input.to_str().unwrap();
+
+    let expected = ["day 1", "  part 1: 11", "  part 2: 12"];
+    let vocabulary = "day01/vocabularies/german";
+    assert_eq!(
+        answers(&["run", "1", input, "--vocabulary", vocabulary]),
+        expected
+    );
+    assert_eq!(
+        answers(&["run", "--vocabulary", vocabulary, "1", input]),
+        expected
+    );
+}
@@ -0,0 +1,7 @@
+mod error;
+mod grid;
+mod parse_error;
+
+pub use error::Error;
+pub use grid::{Direction, Grid, Point};
+pub use parse_error::{parse_token, ParseError};
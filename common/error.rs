@@ -0,0 +1,52 @@
+use crate::ParseError;
+use std::fmt;
+
+/// Error reported by a day: either its input doesn't have the expected form, or
+/// it does but the puzzle has no answer for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    Parse(ParseError),
+    /// What the input lacks for the puzzle to have an answer.
+    NoSolution(String),
+}
+
+impl Error {
+    pub fn no_solution(reason: &str) -> Self {
+        Error::NoSolution(reason.to_string())
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(e) => e.fmt(f),
+            Error::NoSolution(reason) => write!(f, "no solution: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let e = Error::from(ParseError::new(2, 4, "ef", "a number"));
+        assert_eq!(
+            e.to_string(),
+            "line 2, column 4: expected a number, found \"ef\""
+        );
+        assert_eq!(
+            Error::no_solution("no path reaches the goal").to_string(),
+            "no solution: no path reaches the goal"
+        );
+    }
+}
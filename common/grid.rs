@@ -0,0 +1,343 @@
+use crate::ParseError;
+use std::fmt;
+use std::ops::{Add, Index, IndexMut, Sub};
+
+/// Position in a grid, `y` growing downwards. Coordinates are signed so that
+/// points just outside the grid can be represented.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    pub const fn new(x: isize, y: isize) -> Self {
+        Point { x, y }
+    }
+
+    pub fn step(self, direction: Direction) -> Self {
+        self + direction.delta()
+    }
+
+    pub fn manhattan_distance(self, other: Point) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// The 4 orthogonally adjacent points.
+    pub fn neighbours4(self) -> impl Iterator<Item = Point> {
+        Direction::ALL.into_iter().map(move |d| self.step(d))
+    }
+
+    /// The 8 adjacent points, diagonals included.
+    pub fn neighbours8(self) -> impl Iterator<Item = Point> {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| Point::new(dx, dy)))
+            .filter(|&delta| delta != Point::new(0, 0))
+            .map(move |delta| self + delta)
+    }
+}
+
+impl Add for Point {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub fn delta(self) -> Point {
+        match self {
+            Direction::Up => Point::new(0, -1),
+            Direction::Down => Point::new(0, 1),
+            Direction::Left => Point::new(-1, 0),
+            Direction::Right => Point::new(1, 0),
+        }
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    pub fn turn_left(self) -> Self {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Down,
+            Direction::Right => Direction::Up,
+        }
+    }
+
+    pub fn turn_right(self) -> Self {
+        self.turn_left().opposite()
+    }
+}
+
+/// Rectangular grid stored row by row in a single buffer.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Grid of `width` columns made of `cells`, given row by row.
+    pub fn new(width: usize, cells: Vec<T>) -> Self {
+        assert!(
+            width > 0 && cells.len().is_multiple_of(width),
+            "cells don't fill the grid"
+        );
+
+        Grid {
+            width,
+            height: cells.len() / width,
+            cells,
+        }
+    }
+
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(width, vec![value; width * height])
+    }
+
+    /// Parses a rectangular grid of characters, converting each one with `f`.
+    pub fn parse(
+        input: &str,
+        expected: &str,
+        f: impl Fn(char) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let mut cells = Vec::new();
+        let mut width = None;
+
+        for line in input.lines() {
+            let row_start = cells.len();
+            for (i, c) in line.char_indices() {
+                cells.push(
+                    f(c).ok_or_else(|| {
+                        ParseError::at(input, &line[i..i + c.len_utf8()], expected)
+                    })?,
+                );
+            }
+
+            let row_len = cells.len() - row_start;
+            match width {
+                None => width = Some(row_len),
+                Some(width) if width != row_len => {
+                    let expected = format!("a row of {} characters", width);
+                    return Err(ParseError::at(input, line, &expected));
+                }
+                Some(_) => {}
+            }
+        }
+
+        match width {
+            Some(width) if width > 0 => Ok(Self::new(width, cells)),
+            _ => Err(ParseError::end_of(input, "a grid")),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        (0..self.width as isize).contains(&p.x) && (0..self.height as isize).contains(&p.y)
+    }
+
+    fn offset(&self, p: Point) -> Option<usize> {
+        self.contains(p)
+            .then(|| p.y as usize * self.width + p.x as usize)
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.offset(p).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.offset(p).map(|i| &mut self.cells[i])
+    }
+
+    /// Cell at `p` in the infinite plane made of copies of the grid.
+    pub fn get_wrapped(&self, p: Point) -> &T {
+        let x = p.x.rem_euclid(self.width as isize);
+        let y = p.y.rem_euclid(self.height as isize);
+        &self[Point::new(x, y)]
+    }
+
+    /// All the points of the grid, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| Point::new((i % width) as isize, (i / width) as isize))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    /// First point, row by row, whose cell satisfies `pred`.
+    pub fn position(&self, pred: impl Fn(&T) -> bool) -> Option<Point> {
+        self.iter().find(|(_, c)| pred(c)).map(|(p, _)| p)
+    }
+
+    /// The orthogonal neighbours of `p` within the grid.
+    pub fn neighbours4(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        p.neighbours4().filter(|&n| self.contains(n))
+    }
+
+    /// The neighbours of `p` within the grid, diagonals included.
+    pub fn neighbours8(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        p.neighbours8().filter(|&n| self.contains(n))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid::new(self.width, self.cells.iter().map(f).collect())
+    }
+
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        Grid::new(self.height, self.columns().flatten().cloned().collect())
+    }
+
+    /// The grid turned a quarter clockwise.
+    pub fn rotate_clockwise(&self) -> Self
+    where
+        T: Clone,
+    {
+        Grid::new(
+            self.height,
+            self.columns()
+                .flat_map(|column| column.collect::<Vec<_>>().into_iter().rev())
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Point) -> &T {
+        self.get(p)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", p))
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        self.get_mut(p)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", p))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let digit = |c: char| c.to_digit(10);
+        let grid = Grid::parse("12\n34\n", "a digit", digit).unwrap();
+        assert_eq!((grid.width(), grid.height()), (2, 2));
+        assert_eq!(grid[Point::new(0, 1)], 3);
+        assert_eq!(grid.get(Point::new(2, 0)), None);
+        assert_eq!(
+            Grid::parse("12\n3x\n", "a digit", digit),
+            Err(ParseError::new(2, 2, "x", "a digit"))
+        );
+        assert_eq!(
+            Grid::parse("12\n345\n", "a digit", digit),
+            Err(ParseError::new(2, 1, "345", "a row of 2 characters"))
+        );
+        assert_eq!(
+            Grid::parse("", "a digit", digit),
+            Err(ParseError::new(1, 1, "", "a grid"))
+        );
+    }
+
+    #[test]
+    fn views() {
+        let grid = Grid::new(3, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), vec![2, 5]);
+        assert_eq!(grid.transpose(), Grid::new(2, vec![1, 4, 2, 5, 3, 6]));
+        assert_eq!(
+            grid.rotate_clockwise(),
+            Grid::new(2, vec![4, 1, 5, 2, 6, 3])
+        );
+        assert_eq!(grid.get_wrapped(Point::new(-1, 2)), &3);
+        assert_eq!(grid.to_string(), "123\n456\n");
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = Grid::filled(3, 3, 0);
+        assert_eq!(grid.neighbours4(Point::new(0, 0)).count(), 2);
+        assert_eq!(grid.neighbours8(Point::new(0, 0)).count(), 3);
+        assert_eq!(grid.neighbours8(Point::new(1, 1)).count(), 8);
+    }
+}
@@ -0,0 +1,119 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Error reported when puzzle input doesn't have the expected form. Lines and
+/// columns start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub expected: String,
+}
+
+// Line and column at which `token` starts, `token` being a slice of `input`. Tokens
+// that don't point into `input` are reported at the end of it.
+fn position(input: &str, token: &str) -> (usize, usize) {
+    let offset = (token.as_ptr() as usize)
+        .checked_sub(input.as_ptr() as usize)
+        .filter(|&offset| offset <= input.len())
+        .unwrap_or(input.len());
+    let before = &input[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, token: &str, expected: &str) -> Self {
+        ParseError {
+            line,
+            column,
+            token: token.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+
+    /// Error on `token`, which must be a slice of `input`.
+    pub fn at(input: &str, token: &str, expected: &str) -> Self {
+        let (line, column) = position(input, token);
+        Self::new(line, column, token, expected)
+    }
+
+    /// Error at the end of `input`.
+    pub fn end_of(input: &str, expected: &str) -> Self {
+        Self::at(input, &input[input.len()..], expected)
+    }
+
+    /// Moves an error found while parsing `inner`, a slice of `outer`, to its
+    /// position in `outer`.
+    pub fn rebase(self, outer: &str, inner: &str) -> Self {
+        let (line, column) = position(outer, inner);
+
+        ParseError {
+            column: if self.line == 1 {
+                self.column + column - 1
+            } else {
+                self.column
+            },
+            line: self.line + line - 1,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, found ",
+            self.line, self.column, self.expected
+        )?;
+        if self.token.is_empty() {
+            write!(f, "end of input")
+        } else {
+            write!(f, "{:?}", self.token)
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Parses `token`, a slice of `input`.
+pub fn parse_token<T: FromStr>(input: &str, token: &str, expected: &str) -> Result<T, ParseError> {
+    token
+        .parse()
+        .map_err(|_| ParseError::at(input, token, expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_position() {
+        let input = "ab\ncd ef\n";
+        let e = ParseError::at(input, &input[6..8], "a number");
+        assert_eq!(e, ParseError::new(2, 4, "ef", "a number"));
+        assert_eq!(
+            e.to_string(),
+            "line 2, column 4: expected a number, found \"ef\""
+        );
+        assert_eq!(
+            ParseError::end_of(input, "x").to_string(),
+            "line 3, column 1: expected x, found end of input"
+        );
+    }
+
+    #[test]
+    fn rebased_position() {
+        let input = "Game 1: 3 blue\nGame 2: 4 purple";
+        let set = &input[23..];
+        let e = ParseError::at(set, &set[2..], "a colour").rebase(input, set);
+        assert_eq!((e.line, e.column), (2, 11));
+    }
+}
@@ -0,0 +1,170 @@
+// Aho–Corasick automaton: finds every occurrence of a set of patterns, overlapping
+// ones included, in a single pass over the text.
+
+const ROOT: usize = 0;
+
+#[derive(Default)]
+struct Node {
+    // transitions sorted by byte
+    next: Vec<(u8, usize)>,
+    // longest proper suffix of this node's string that is also in the trie
+    fail: usize,
+    // value and length of the pattern ending here
+    output: Option<(usize, usize)>,
+    // nearest node on the fail chain with an output
+    dict: Option<usize>,
+}
+
+impl Node {
+    fn goto(&self, b: u8) -> Option<usize> {
+        self.next
+            .binary_search_by_key(&b, |&(byte, _)| byte)
+            .ok()
+            .map(|i| self.next[i].1)
+    }
+}
+
+pub struct Automaton {
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub start: usize,
+    pub len: usize,
+    pub value: usize,
+}
+
+impl Automaton {
+    /// Builds the automaton for `patterns`, each mapped to a value. Empty patterns
+    /// are ignored and a repeated pattern keeps its last value.
+    pub fn new<S: AsRef<str>>(patterns: impl IntoIterator<Item = (S, usize)>) -> Self {
+        let mut nodes = vec![Node::default()];
+
+        for (pattern, value) in patterns {
+            let pattern = pattern.as_ref().as_bytes();
+            if pattern.is_empty() {
+                continue;
+            }
+
+            let mut node = ROOT;
+            for &b in pattern {
+                node = match nodes[node].goto(b) {
+                    Some(next) => next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        let transitions = &mut nodes[node].next;
+                        let i = transitions.partition_point(|&(byte, _)| byte < b);
+                        transitions.insert(i, (b, next));
+                        next
+                    }
+                };
+            }
+            nodes[node].output = Some((value, pattern.len()));
+        }
+
+        // fail links, breadth first so that shorter strings are done first
+        let mut queue: Vec<usize> = nodes[ROOT].next.iter().map(|&(_, n)| n).collect();
+        let mut i = 0;
+        while i < queue.len() {
+            let node = queue[i];
+            i += 1;
+
+            for (b, child) in nodes[node].next.clone() {
+                let mut fail = nodes[node].fail;
+                while fail != ROOT && nodes[fail].goto(b).is_none() {
+                    fail = nodes[fail].fail;
+                }
+                let fail = nodes[fail].goto(b).filter(|&n| n != child).unwrap_or(ROOT);
+
+                nodes[child].fail = fail;
+                nodes[child].dict = if nodes[fail].output.is_some() {
+                    Some(fail)
+                } else {
+                    nodes[fail].dict
+                };
+                queue.push(child);
+            }
+        }
+
+        Automaton { nodes }
+    }
+
+    fn step(&self, mut state: usize, b: u8) -> usize {
+        loop {
+            if let Some(next) = self.nodes[state].goto(b) {
+                return next;
+            }
+            if state == ROOT {
+                return ROOT;
+            }
+            state = self.nodes[state].fail;
+        }
+    }
+
+    /// All the matches in `haystack`, overlapping ones included, ordered by end.
+    pub fn find_iter<'a>(&'a self, haystack: &'a str) -> Matches<'a> {
+        Matches {
+            automaton: self,
+            haystack: haystack.as_bytes(),
+            pos: 0,
+            state: ROOT,
+            pending: None,
+        }
+    }
+}
+
+pub struct Matches<'a> {
+    automaton: &'a Automaton,
+    haystack: &'a [u8],
+    pos: usize,
+    state: usize,
+    // next node whose output ends at `pos`
+    pending: Option<usize>,
+}
+
+impl Iterator for Matches<'_> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            if let Some(node) = self.pending {
+                let node = &self.automaton.nodes[node];
+                self.pending = node.dict;
+                let (value, len) = node.output.unwrap();
+                return Some(Match {
+                    start: self.pos - len,
+                    len,
+                    value,
+                });
+            }
+
+            let &b = self.haystack.get(self.pos)?;
+            self.state = self.automaton.step(self.state, b);
+            self.pos += 1;
+
+            let node = &self.automaton.nodes[self.state];
+            self.pending = if node.output.is_some() {
+                Some(self.state)
+            } else {
+                node.dict
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_matches() {
+        let automaton = Automaton::new([("two", 2), ("one", 1), ("ne", 0), ("twone", 21)]);
+        let matches: Vec<(usize, usize)> = automaton
+            .find_iter("xtwonex")
+            .map(|m| (m.start, m.value))
+            .collect();
+        assert_eq!(matches, vec![(1, 2), (1, 21), (3, 1), (4, 0)]);
+    }
+}
@@ -1,72 +1,215 @@
-use std::collections::HashMap;
-use std::io;
-
-fn find_calibration_value(s: &str, digits: &HashMap<&str, usize>) -> usize {
-    let first = digits
-        .iter()
-        .map(|(digit_str, digit)| (s.find(digit_str), digit))
-        .filter(|(index, _)| index.is_some())
-        .min_by_key(|(index, _)| index.unwrap())
-        .unwrap()
-        .1;
-    let last = digits
-        .iter()
-        .map(|(digit_str, digit)| (s.rfind(digit_str), digit))
-        .filter(|(index, _)| index.is_some())
-        .max_by_key(|(index, _)| index.unwrap())
-        .unwrap()
-        .1;
-
-    first * 10 + last
+mod aho_corasick;
+
+use aho_corasick::Automaton;
+use common::{parse_token, Error, ParseError};
+use std::cmp::Reverse;
+use std::str::FromStr;
+
+const DIGITS: [(&str, usize); 9] = [
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
+    ("9", 9),
+];
+
+/// Words standing for numbers, matched in addition to the digits. A vocabulary
+/// file has a word and its value per line; `#` starts a comment.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Vocabulary {
+    words: Vec<(String, usize)>,
 }
 
-fn main() {
-    let lines: Vec<_> = io::stdin().lines().map(|line| line.unwrap()).collect();
-
-    let digits: HashMap<&str, usize> = HashMap::from([
-        ("1", 1),
-        ("2", 2),
-        ("3", 3),
-        ("4", 4),
-        ("5", 5),
-        ("6", 6),
-        ("7", 7),
-        ("8", 8),
-        ("9", 9),
-    ]);
-    println!(
-        "part 1: {}",
-        lines
+impl Vocabulary {
+    fn automaton(&self) -> Automaton {
+        let words = self
+            .words
             .iter()
-            .map(|line| find_calibration_value(line, &digits))
-            .sum::<usize>()
-    );
-
-    let digits_spelled: HashMap<&str, usize> = HashMap::from([
-        ("1", 1),
-        ("2", 2),
-        ("3", 3),
-        ("4", 4),
-        ("5", 5),
-        ("6", 6),
-        ("7", 7),
-        ("8", 8),
-        ("9", 9),
-        ("one", 1),
-        ("two", 2),
-        ("three", 3),
-        ("four", 4),
-        ("five", 5),
-        ("six", 6),
-        ("seven", 7),
-        ("eight", 8),
-        ("nine", 9),
-    ]);
-    println!(
-        "part 2: {}",
-        lines
-            .iter()
-            .map(|line| find_calibration_value(line, &digits_spelled))
-            .sum::<usize>()
-    );
+            .map(|(word, value)| (word.as_str(), *value));
+        Automaton::new(DIGITS.into_iter().chain(words))
+    }
+}
+
+impl Default for Vocabulary {
+    // the English spellings of part 2
+    fn default() -> Self {
+        include_str!("vocabularies/english")
+            .parse()
+            .expect("invalid built-in vocabulary")
+    }
+}
+
+impl FromStr for Vocabulary {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut words: Vec<(String, usize)> = Vec::new();
+
+        for line in input.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let (word, value) = match tokens[..] {
+                [] => continue,
+                [word, value] => (word, value),
+                _ => return Err(ParseError::at(input, line, "\"<word> <value>\"")),
+            };
+
+            // digits are always recognized and can't be redefined
+            if word.contains(|c: char| c.is_ascii_digit()) {
+                return Err(ParseError::at(input, word, "a word without digits"));
+            }
+            if words.iter().any(|(w, _)| w == word) {
+                return Err(ParseError::at(input, word, "a word not defined before"));
+            }
+            let value = parse_token(input, value, "a number")?;
+
+            words.push((word.to_string(), value));
+        }
+
+        Ok(Vocabulary { words })
+    }
+}
+
+fn leading_digit(mut n: usize) -> usize {
+    while n >= 10 {
+        n /= 10;
+    }
+    n
+}
+
+fn find_calibration_value(s: &str, digits: &Automaton) -> Option<usize> {
+    let mut matches = digits.find_iter(s);
+    let m = matches.next()?;
+
+    // the first digit starts first and the last one starts last, the longest
+    // spelling winning when several start at the same place
+    let (first, last) = matches.fold((m, m), |(first, last), m| {
+        let first = if (m.start, Reverse(m.len)) < (first.start, Reverse(first.len)) {
+            m
+        } else {
+            first
+        };
+        let last = if (m.start, m.len) > (last.start, last.len) {
+            m
+        } else {
+            last
+        };
+        (first, last)
+    });
+
+    // a number of several digits is read as if it was written with digits
+    Some(leading_digit(first.value) * 10 + last.value % 10)
+}
+
+fn calibration_sum(input: &str, digits: &Automaton) -> Result<usize, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            find_calibration_value(line, digits)
+                .ok_or_else(|| ParseError::at(input, line, "a line containing a digit"))
+        })
+        .sum()
+}
+
+/// The calibration document, whose lines are scanned as they're read, leaving
+/// nothing to parse ahead of the parts.
+pub struct Parsed<'a>(&'a str);
+
+pub fn parse_input(input: &str) -> Result<Parsed<'_>, ParseError> {
+    Ok(Parsed(input))
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    Ok(calibration_sum(parsed.0, &Automaton::new(DIGITS))?)
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    part2_with_vocabulary(parsed.0, &Vocabulary::default())
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+/// Part 2 with the words of `vocabulary` instead of the English digits.
+pub fn part2_with_vocabulary(input: &str, vocabulary: &Vocabulary) -> Result<usize, Error> {
+    Ok(calibration_sum(input, &vocabulary.automaton())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+    const TEST_INPUT1: &str = include_str!("test_input1");
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(142));
+    }
+
+    #[test]
+    fn part2_test_input1() {
+        assert_eq!(part2(TEST_INPUT1), Ok(281));
+    }
+
+    #[test]
+    fn overlapping_spellings() {
+        let digits = Vocabulary::default().automaton();
+        assert_eq!(find_calibration_value("twone", &digits), Some(21));
+        assert_eq!(find_calibration_value("eightwothree", &digits), Some(83));
+        assert_eq!(find_calibration_value("7pqrstsixteen", &digits), Some(76));
+    }
+
+    #[test]
+    fn line_without_digits() {
+        assert_eq!(
+            part1("1abc2\nabc\n"),
+            Err(ParseError::new(2, 1, "abc", "a line containing a digit").into())
+        );
+    }
+
+    #[test]
+    fn other_vocabularies() {
+        let german: Vocabulary = include_str!("vocabularies/german").parse().unwrap();
+        assert_eq!(
+            part2_with_vocabulary("xzweitex7\nachtfünf\n", &german),
+            Ok(27 + 85)
+        );
+
+        let extended: Vocabulary = include_str!("vocabularies/english-extended")
+            .parse()
+            .unwrap();
+        assert_eq!(part2_with_vocabulary("twelvex3\n", &extended), Ok(13));
+        assert_eq!(part2_with_vocabulary("4firstten\n", &extended), Ok(40));
+        assert_eq!(part2_with_vocabulary("sixteen\n", &extended), Ok(16));
+    }
+
+    #[test]
+    fn invalid_vocabularies() {
+        assert_eq!(
+            "one 1\ntwo\n".parse::<Vocabulary>(),
+            Err(ParseError::new(2, 1, "two", "\"<word> <value>\""))
+        );
+        assert_eq!(
+            "one 1 # comment\none 2\n".parse::<Vocabulary>(),
+            Err(ParseError::new(2, 1, "one", "a word not defined before"))
+        );
+        assert_eq!(
+            "un1 1\n".parse::<Vocabulary>(),
+            Err(ParseError::new(1, 1, "un1", "a word without digits"))
+        );
+        assert_eq!(
+            "one x\n".parse::<Vocabulary>(),
+            Err(ParseError::new(1, 5, "x", "a number"))
+        );
+    }
 }
@@ -1,38 +1,64 @@
-use std::io;
+use common::{parse_token, Error, ParseError};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug)]
-struct CubeSet {
-    red: usize,
-    green: usize,
-    blue: usize,
+// the bag of part 1
+const BAG: &str = "12 red, 13 green, 14 blue";
+
+/// Number of cubes of each colour, e.g. "3 blue, 4 red".
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CubeSet {
+    cubes: BTreeMap<String, usize>,
 }
 
-#[derive(Debug)]
-struct ParseCubeSetError;
+impl CubeSet {
+    pub fn count(&self, colour: &str) -> usize {
+        self.cubes.get(colour).copied().unwrap_or(0)
+    }
+
+    /// Whether the cubes could all have been drawn from `bag`.
+    pub fn fits_in(&self, bag: &CubeSet) -> bool {
+        self.cubes.iter().all(|(colour, &n)| n <= bag.count(colour))
+    }
+
+    /// The smallest set containing both `self` and `other`.
+    fn union(mut self, other: &CubeSet) -> CubeSet {
+        for (colour, &n) in &other.cubes {
+            let count = self.cubes.entry(colour.clone()).or_default();
+            *count = (*count).max(n);
+        }
+        self
+    }
+
+    /// The number of red, green and blue cubes multiplied together, none of a
+    /// colour making it 0.
+    pub fn power(&self) -> usize {
+        self.count("red") * self.count("green") * self.count("blue")
+    }
+
+    // the counts of every colour in the set multiplied together
+    fn power_of_present(&self) -> usize {
+        self.cubes.values().product()
+    }
+}
 
 impl FromStr for CubeSet {
-    type Err = ParseCubeSetError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut cs = CubeSet {
-            red: 0,
-            green: 0,
-            blue: 0,
-        };
+        let mut cs = CubeSet::default();
         let mut it = s.split(' ');
 
         while let Some(n_str) = it.next() {
-            let n: usize = n_str.parse().or(Err(ParseCubeSetError))?;
-            if let Some(color) = it.next() {
-                match color.trim_end_matches(',') {
-                    "red" => cs.red = n,
-                    "green" => cs.green = n,
-                    "blue" => cs.blue = n,
-                    _ => return Err(ParseCubeSetError),
-                }
-            } else {
-                return Err(ParseCubeSetError);
+            let n: usize = parse_token(s, n_str, "a number of cubes")?;
+            let colour = it.next().ok_or_else(|| ParseError::end_of(s, "a colour"))?;
+            let colour = colour.trim_end_matches(',');
+            if colour.is_empty() || !colour.chars().all(char::is_alphabetic) {
+                return Err(ParseError::at(s, colour, "a colour"));
+            }
+            if cs.cubes.insert(colour.to_string(), n).is_some() {
+                return Err(ParseError::at(s, colour, "a colour not already in the set"));
             }
         }
 
@@ -40,65 +66,177 @@ impl FromStr for CubeSet {
     }
 }
 
+impl fmt::Display for CubeSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cubes: Vec<String> = self
+            .cubes
+            .iter()
+            .map(|(colour, n)| format!("{} {}", n, colour))
+            .collect();
+        write!(f, "{}", cubes.join(", "))
+    }
+}
+
 #[derive(Debug)]
 struct Game {
     id: usize,
     sets: Vec<CubeSet>,
 }
 
-#[derive(Debug)]
-struct ParseGameError;
-
 impl FromStr for Game {
-    type Err = ParseGameError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let colon = s.find(':').ok_or(ParseGameError)?;
-        let id: usize = s[..colon]
-            .strip_prefix("Game")
-            .and_then(|s| s.trim().parse().ok())
-            .ok_or(ParseGameError)?;
-        let sets: Vec<CubeSet> = s[colon + 1..]
-            .split(";")
-            .map(|s| s.trim().parse().expect("error parsing set"))
-            .collect();
+        let (game, sets) = s
+            .split_once(": ")
+            .ok_or_else(|| ParseError::at(s, s, "\"Game <id>: <sets>\""))?;
+        let id = game
+            .strip_prefix("Game ")
+            .ok_or_else(|| ParseError::at(s, game, "\"Game <id>\""))?;
+        let id: usize = parse_token(s, id, "a game id")?;
+        let sets = sets
+            .split("; ")
+            .map(|set| set.parse().map_err(|e: ParseError| e.rebase(s, set)))
+            .collect::<Result<Vec<CubeSet>, ParseError>>()?;
 
-        return Ok(Game { id, sets });
+        Ok(Game { id, sets })
     }
 }
 
-fn main() {
-    let games: Vec<Game> = io::stdin()
+impl Game {
+    fn is_possible(&self, bag: &CubeSet) -> bool {
+        self.sets.iter().all(|set| set.fits_in(bag))
+    }
+
+    fn minimum_bag(&self) -> CubeSet {
+        self.sets.iter().fold(CubeSet::default(), CubeSet::union)
+    }
+}
+
+fn parse(input: &str) -> Result<Vec<Game>, ParseError> {
+    input
         .lines()
-        .map(|line| line.unwrap().parse().expect("error parsing game"))
+        .map(|line| line.parse().map_err(|e: ParseError| e.rebase(input, line)))
+        .collect()
+}
+
+/// The games with the cube sets revealed in each.
+pub struct Parsed(Vec<Game>);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    let bag: CubeSet = BAG.parse()?;
+
+    Ok(parsed
+        .0
+        .iter()
+        .filter(|game| game.is_possible(&bag))
+        .map(|game| game.id)
+        .sum::<usize>())
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    Ok(parsed
+        .0
+        .iter()
+        .map(|game| game.minimum_bag().power())
+        .sum::<usize>())
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+/// The games possible with `bag`, followed by the minimum bag of every game and
+/// the power of the colours in it.
+pub fn query(input: &str, bag: &CubeSet) -> Result<String, ParseError> {
+    let games = parse(input)?;
+
+    let possible: Vec<String> = games
+        .iter()
+        .filter(|game| game.is_possible(bag))
+        .map(|game| game.id.to_string())
         .collect();
+    let mut report = format!("possible with {}: {}\n", bag, possible.join(", "));
 
-    println!(
-        "part 1: {}",
-        games
-            .iter()
-            .filter(|game| game
-                .sets
-                .iter()
-                .all(|set| set.red <= 12 && set.green <= 13 && set.blue <= 14))
-            .map(|game| game.id)
-            .sum::<usize>()
-    );
-
-    println!(
-        "part 2: {}",
-        games
-            .into_iter()
-            .map(|game| game
-                .sets
-                .into_iter()
-                .reduce(|acc, e| CubeSet {
-                    red: acc.red.max(e.red),
-                    green: acc.green.max(e.green),
-                    blue: acc.blue.max(e.blue),
-                })
-                .unwrap())
-            .map(|min_set| min_set.red * min_set.green * min_set.blue)
-            .sum::<usize>()
-    );
+    for game in &games {
+        let minimum_bag = game.minimum_bag();
+        report += &format!(
+            "game {}: minimum bag {}, power {}\n",
+            game.id,
+            minimum_bag,
+            minimum_bag.power_of_present()
+        );
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn parse_cube_set() {
+        let cs: CubeSet = "3 blue, 4 red, 1 yellow".parse().unwrap();
+        assert_eq!(
+            (cs.count("red"), cs.count("green"), cs.count("yellow")),
+            (4, 0, 1)
+        );
+        assert_eq!(cs.to_string(), "3 blue, 4 red, 1 yellow");
+        assert_eq!(
+            "3 blue, 4 blue".parse::<CubeSet>(),
+            Err(ParseError::new(
+                1,
+                11,
+                "blue",
+                "a colour not already in the set"
+            ))
+        );
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(8));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(2286));
+    }
+
+    #[test]
+    fn other_colours() {
+        let input = "Game 1: 3 blue, 4 red\nGame 2: 1 red; 2 purple, 1 blue\n";
+        assert_eq!(part1(input), Ok(1));
+        // no green cubes are ever shown
+        assert_eq!(part2(input), Ok(0));
+
+        let bag: CubeSet = "1 red, 3 blue, 2 purple".parse().unwrap();
+        assert_eq!(
+            query(input, &bag),
+            Ok("possible with 3 blue, 2 purple, 1 red: 2\n\
+                game 1: minimum bag 3 blue, 4 red, power 12\n\
+                game 2: minimum bag 1 blue, 2 purple, 1 red, power 2\n"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_colour() {
+        let input = "Game 1: 3 blue, 4 red\nGame 2: 1 red; 2 ye11ow, 1 blue\n";
+        assert_eq!(
+            part1(input),
+            Err(ParseError::new(2, 18, "ye11ow", "a colour").into())
+        );
+    }
 }
@@ -1,32 +1,43 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::io;
+use common::{parse_token, Error, Grid, ParseError, Point};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+// highlighting of the annotated schematic
+const PART_NUMBER_STYLE: &str = "\x1b[1;32m";
+const ORPHAN_STYLE: &str = "\x1b[2m";
+const MATCHED_SYMBOL_STYLE: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
 
 struct Number {
-    y: usize,
-    x: usize,
+    start: Point,
     len: usize,
     value: usize,
 }
 
-fn find_numbers(schematic: &[Vec<char>]) -> Vec<Number> {
+impl Number {
+    fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        (0..self.len as isize).map(|dx| Point::new(self.start.x + dx, self.start.y))
+    }
+}
+
+fn find_numbers(schematic: &Grid<char>) -> Vec<Number> {
     let mut numbers = Vec::new();
 
-    for (y, line) in schematic.iter().enumerate() {
+    for (y, line) in schematic.rows().enumerate() {
         let mut x: usize = 0;
 
         while x < line.len() {
-            if line[x].is_digit(10) {
+            if line[x].is_ascii_digit() {
                 let mut j = x;
                 let mut value = 0;
-                while j < line.len() && line[j].is_digit(10) {
+                while j < line.len() && line[j].is_ascii_digit() {
                     value = value * 10 + line[j].to_digit(10).unwrap() as usize;
                     j += 1;
                 }
 
                 numbers.push(Number {
-                    y,
-                    x,
+                    start: Point::new(x as isize, y as isize),
                     len: j - x,
                     value,
                 });
@@ -41,86 +52,328 @@ fn find_numbers(schematic: &[Vec<char>]) -> Vec<Number> {
     numbers
 }
 
-fn adjacent_pos(
-    y: usize,
-    x: usize,
-    schematic: &[Vec<char>],
-) -> impl Iterator<Item = (usize, usize)> + '_ {
-    [
-        (-1, -1),
-        (-1, 0),
-        (-1, 1),
-        (0, -1),
-        (0, 1),
-        (1, -1),
-        (1, 0),
-        (1, 1),
-    ]
-    .iter()
-    .filter_map(move |(dy, dx)| {
-        let ny = y as isize + dy;
-        let nx = x as isize + dx;
-        if ny >= 0
-            && (ny as usize) < schematic.len()
-            && nx >= 0
-            && (nx as usize) < schematic[ny as usize].len()
-        {
-            Some((ny as usize, nx as usize))
-        } else {
-            None
+fn is_symbol(c: char) -> bool {
+    c != '.' && !c.is_ascii_digit()
+}
+
+/// Symbols with a given number of adjacent numbers, e.g. "gear:*:2" for the
+/// gears of part 2: `*` symbols next to exactly two numbers.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rule {
+    name: String,
+    symbols: Vec<char>,
+    arity: usize,
+}
+
+impl Rule {
+    fn matches(&self, symbol: &Symbol) -> bool {
+        self.symbols.contains(&symbol.c) && symbol.numbers.len() == self.arity
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format = "\"<name>:<symbols>:<arity>\"";
+        let (rest, arity) = s
+            .rsplit_once(':')
+            .ok_or_else(|| ParseError::at(s, s, format))?;
+        let (name, symbols) = rest
+            .split_once(':')
+            .ok_or_else(|| ParseError::at(s, s, format))?;
+
+        if symbols.is_empty() || !symbols.chars().all(is_symbol) {
+            return Err(ParseError::at(
+                s,
+                symbols,
+                "symbols other than digits and '.'",
+            ));
         }
-    })
+        let arity = parse_token(s, arity, "a number of adjacent numbers")?;
+
+        Ok(Rule {
+            name: name.to_string(),
+            symbols: symbols.chars().collect(),
+            arity,
+        })
+    }
 }
 
-fn adjacent_chars(y: usize, x: usize, schematic: &[Vec<char>]) -> impl Iterator<Item = char> + '_ {
-    adjacent_pos(y, x, schematic).map(move |(y, x)| schematic[y][x])
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbols: String = self.symbols.iter().collect();
+        write!(f, "{} ({} with {} numbers)", self.name, symbols, self.arity)
+    }
 }
 
-fn main() {
-    let schematic: Vec<Vec<char>> = io::stdin()
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect();
+struct Symbol {
+    position: Point,
+    c: char,
+    // indices of the adjacent numbers
+    numbers: BTreeSet<usize>,
+}
+
+// The schematic with the graph linking each symbol to its adjacent numbers.
+struct Analysis {
+    schematic: Grid<char>,
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    // whether each number is adjacent to a symbol
+    is_part_number: Vec<bool>,
+}
+
+impl Analysis {
+    fn new(schematic: Grid<char>) -> Self {
+        let numbers = find_numbers(&schematic);
+        let number_at: HashMap<Point, usize> = numbers
+            .iter()
+            .enumerate()
+            .flat_map(|(i, number)| number.points().map(move |p| (p, i)))
+            .collect();
 
-    let numbers = find_numbers(&schematic);
-    let part_numbers: Vec<&Number> = numbers
-        .iter()
-        .filter(|number| {
-            (number.x..number.x + number.len).any(|x| {
-                adjacent_chars(number.y, x, &schematic).any(|c| c != '.' && !c.is_digit(10))
+        let symbols: Vec<Symbol> = schematic
+            .iter()
+            .filter(|(_, &c)| is_symbol(c))
+            .map(|(p, &c)| Symbol {
+                position: p,
+                c,
+                numbers: schematic
+                    .neighbours8(p)
+                    .filter_map(|n| number_at.get(&n).copied())
+                    .collect(),
             })
-        })
-        .collect();
-    println!(
-        "part 1: {}",
+            .collect();
+
+        let mut is_part_number = vec![false; numbers.len()];
+        for &i in symbols.iter().flat_map(|symbol| &symbol.numbers) {
+            is_part_number[i] = true;
+        }
+
+        Analysis {
+            schematic,
+            numbers,
+            symbols,
+            is_part_number,
+        }
+    }
+
+    fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.is_part_number)
+            .filter(|(_, &is_part_number)| is_part_number)
+            .map(|(number, _)| number)
+    }
+
+    // numbers adjacent to no symbol
+    fn orphans(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.is_part_number)
+            .filter(|(_, &is_part_number)| !is_part_number)
+            .map(|(number, _)| number)
+    }
+
+    // symbols matched by `rule` with the product of their numbers
+    fn ratios<'a>(&'a self, rule: &'a Rule) -> impl Iterator<Item = (&'a Symbol, usize)> {
+        self.symbols
+            .iter()
+            .filter(|symbol| rule.matches(symbol))
+            .map(|symbol| {
+                let ratio = symbol
+                    .numbers
+                    .iter()
+                    .map(|&i| self.numbers[i].value)
+                    .product();
+                (symbol, ratio)
+            })
+    }
+
+    // the schematic with part numbers, orphans and matched symbols highlighted
+    fn annotate(&self, rules: &[Rule]) -> String {
+        let mut styles: HashMap<Point, &str> = HashMap::new();
+        for (number, &is_part_number) in self.numbers.iter().zip(&self.is_part_number) {
+            let style = if is_part_number {
+                PART_NUMBER_STYLE
+            } else {
+                ORPHAN_STYLE
+            };
+            styles.extend(number.points().map(|p| (p, style)));
+        }
+        for rule in rules {
+            for (symbol, _) in self.ratios(rule) {
+                styles.insert(symbol.position, MATCHED_SYMBOL_STYLE);
+            }
+        }
+
+        let mut annotated = String::new();
+        for (y, row) in self.schematic.rows().enumerate() {
+            let mut current = None;
+            for (x, &c) in row.iter().enumerate() {
+                let style = styles.get(&Point::new(x as isize, y as isize)).copied();
+                if style != current {
+                    annotated += style.unwrap_or(RESET);
+                    current = style;
+                }
+                annotated.push(c);
+            }
+            if current.is_some() {
+                annotated += RESET;
+            }
+            annotated.push('\n');
+        }
+
+        annotated
+    }
+}
+
+fn parse(input: &str) -> Result<Grid<char>, ParseError> {
+    Grid::parse(input, "a schematic character", Some)
+}
+
+/// The schematic with its numbers and symbols located.
+pub struct Parsed(Analysis);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    Ok(Parsed(Analysis::new(parse(input)?)))
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    Ok(parsed
+        .0
+        .part_numbers()
+        .map(|number| number.value)
+        .sum::<usize>())
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let gear: Rule = "gear:*:2".parse()?;
+
+    Ok(parsed
+        .0
+        .ratios(&gear)
+        .map(|(_, ratio)| ratio)
+        .sum::<usize>())
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+/// The annotated schematic followed by the orphan numbers and, for each rule,
+/// the symbols it matches and the sum of their ratios.
+pub fn analyze(input: &str, rules: &[Rule]) -> Result<String, ParseError> {
+    let analysis = Analysis::new(parse(input)?);
+    let mut report = analysis.annotate(rules);
+
+    let part_numbers: Vec<&Number> = analysis.part_numbers().collect();
+    report += &format!(
+        "part numbers: {}, sum {}\n",
+        part_numbers.len(),
         part_numbers
             .iter()
             .map(|number| number.value)
             .sum::<usize>()
     );
 
-    let part_numbers_positions: HashMap<(usize, usize), usize> = part_numbers
-        .iter()
-        .enumerate()
-        .map(|(i, number)| (number.x..number.x + number.len).map(move |x| ((number.y, x), i)))
-        .flatten()
-        .collect();
-    let gear_ratios: Vec<usize> = schematic
-        .iter()
-        .map(|row| row.iter())
-        .flatten()
-        .enumerate()
-        .filter(|(_, &c)| c == '*')
-        .map(|(pos, _)| {
-            let y = pos / schematic[0].len();
-            let x = pos % schematic[0].len();
-            adjacent_pos(y, x, &schematic)
-                .filter_map(|adj_pos| part_numbers_positions.get(&adj_pos))
-                .map(|index| *index)
-                .collect::<HashSet<usize>>()
+    let orphans: Vec<String> = analysis
+        .orphans()
+        .map(|number| {
+            format!(
+                "{} (line {}, column {})",
+                number.value,
+                number.start.y + 1,
+                number.start.x + 1
+            )
         })
-        .filter(|set| set.len() == 2)
-        .map(|set| set.iter().fold(1, |acc, &i| acc * part_numbers[i].value))
         .collect();
-    println!("part 2: {}", gear_ratios.iter().sum::<usize>());
+    report += &format!("orphans: {}\n", orphans.join(", "));
+
+    for rule in rules {
+        let ratios: Vec<(&Symbol, usize)> = analysis.ratios(rule).collect();
+        report += &format!(
+            "{}: {} matches, sum of ratios {}\n",
+            rule,
+            ratios.len(),
+            ratios.iter().map(|(_, ratio)| ratio).sum::<usize>()
+        );
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn numbers() {
+        let numbers = find_numbers(&parse(TEST_INPUT).unwrap());
+        assert_eq!(numbers.len(), 10);
+        assert_eq!(
+            (numbers[0].start, numbers[0].value),
+            (Point::new(0, 0), 467)
+        );
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(4361));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(467835));
+    }
+
+    #[test]
+    fn rules() {
+        let analysis = Analysis::new(parse("1.2..\n.#...\n3..4*\n").unwrap());
+        let hub: Rule = "hub:#$:3".parse().unwrap();
+        let ratios: Vec<usize> = analysis.ratios(&hub).map(|(_, ratio)| ratio).collect();
+        assert_eq!(ratios, vec![6]);
+
+        let orphans: Vec<usize> = analysis.orphans().map(|number| number.value).collect();
+        assert_eq!(orphans, vec![]);
+        assert_eq!(
+            analysis.annotate(&[hub]),
+            "\x1b[1;32m1\x1b[0m.\x1b[1;32m2\x1b[0m..\n\
+             .\x1b[1;33m#\x1b[0m...\n\
+             \x1b[1;32m3\x1b[0m..\x1b[1;32m4\x1b[0m*\n"
+        );
+
+        assert_eq!(
+            "hub:#1:3".parse::<Rule>(),
+            Err(ParseError::new(
+                1,
+                5,
+                "#1",
+                "symbols other than digits and '.'"
+            ))
+        );
+        assert_eq!(
+            "colon:::x".parse::<Rule>(),
+            Err(ParseError::new(1, 9, "x", "a number of adjacent numbers"))
+        );
+    }
+
+    #[test]
+    fn orphans() {
+        let analysis = Analysis::new(parse(TEST_INPUT).unwrap());
+        let orphans: Vec<(Point, usize)> = analysis
+            .orphans()
+            .map(|number| (number.start, number.value))
+            .collect();
+        assert_eq!(
+            orphans,
+            vec![(Point::new(5, 0), 114), (Point::new(7, 5), 58)]
+        );
+    }
 }
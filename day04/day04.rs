@@ -1,47 +1,206 @@
-use std::collections::HashMap;
+use common::{parse_token, Error, ParseError};
 use std::collections::HashSet;
-use std::io;
 
-fn main() {
-    let cards_matching_numbers_count: HashMap<usize, usize> = io::stdin()
+struct Card {
+    id: usize,
+    // count of winning numbers the card has
+    matches: usize,
+}
+
+// Parses a card, which must be card `expected_id`.
+fn parse_card(line: &str, expected_id: usize) -> Result<Card, ParseError> {
+    let (card, numbers) = line
+        .split_once(':')
+        .ok_or_else(|| ParseError::at(line, line, "\"Card <id>: <numbers> | <numbers>\""))?;
+    let id_str = card
+        .strip_prefix("Card")
+        .ok_or_else(|| ParseError::at(line, card, "\"Card <id>\""))?
+        .trim_start();
+    let id = parse_token(line, id_str, "a card id")?;
+    if id != expected_id {
+        return Err(ParseError::at(
+            line,
+            id_str,
+            &format!("card {}", expected_id),
+        ));
+    }
+    let (winning, numbers) = numbers
+        .split_once('|')
+        .ok_or_else(|| ParseError::end_of(line, "\"|\""))?;
+
+    let parse_set = |set_str: &str| {
+        set_str
+            .split_whitespace()
+            .map(|n_str| parse_token(line, n_str, "a number"))
+            .collect::<Result<HashSet<u64>, ParseError>>()
+    };
+    let matches = parse_set(winning)?
+        .intersection(&parse_set(numbers)?)
+        .count();
+
+    Ok(Card { id, matches })
+}
+
+// The cards, numbered from 1 without gaps.
+fn parse(input: &str) -> Result<Vec<Card>, ParseError> {
+    input
         .lines()
-        .map(|line| {
-            let line = line.unwrap();
-            let colon = line.find(':').expect("error parsing card");
-            let id = line[..colon]
-                .strip_prefix("Card")
-                .and_then(|s| s.trim().parse().ok())
-                .expect("error parsing card");
-            let matching_numbers_count = line[colon + 1..]
-                .split('|')
-                .map(|set_str| {
-                    set_str
-                        .split_whitespace()
-                        .map(|n_str| n_str.parse::<u64>().unwrap())
-                        .collect::<HashSet<u64>>()
-                })
-                .reduce(|acc, set| acc.intersection(&set).map(|v| *v).collect())
-                .unwrap_or(HashSet::new())
-                .len();
-
-            (id, matching_numbers_count)
-        })
-        .collect();
-    println!(
-        "part 1: {}",
-        cards_matching_numbers_count
-            .iter()
-            .filter(|(_, &count)| count > 0)
-            .map(|(_, &count)| 2usize.pow(count as u32 - 1))
-            .sum::<usize>()
-    );
-
-    let mut instances = vec![1; cards_matching_numbers_count.len()];
-    for card_id in 0..cards_matching_numbers_count.len() {
-        let matching_count = cards_matching_numbers_count.get(&(card_id + 1)).unwrap();
-        for copy_id in card_id + 1..=card_id + matching_count {
-            instances[copy_id] += instances[card_id];
+        .enumerate()
+        .map(|(i, line)| parse_card(line, i + 1).map_err(|e| e.rebase(input, line)))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct CardTrace {
+    // the original card and its copies
+    instances: usize,
+    // copies of the following cards won by all the instances
+    won: usize,
+    // copies that would have been of cards past the last one
+    overflow: usize,
+}
+
+// Every instance of a card wins one copy of each of the next `matches` cards.
+// Copies past the end of the table are counted as overflow rather than won, and
+// counts saturate instead of overflowing.
+fn cascade(cards: &[Card]) -> Vec<CardTrace> {
+    let mut trace = vec![
+        CardTrace {
+            instances: 1,
+            ..Default::default()
+        };
+        cards.len()
+    ];
+
+    for (i, card) in cards.iter().enumerate() {
+        let instances = trace[i].instances;
+        let last = (i + card.matches).min(cards.len() - 1);
+        for next in &mut trace[i + 1..=last] {
+            next.instances = next.instances.saturating_add(instances);
         }
+        trace[i].won = instances.saturating_mul(last - i);
+        trace[i].overflow = instances.saturating_mul(i + card.matches - last);
+    }
+
+    trace
+}
+
+/// The scratchcards with their winning and owned numbers.
+pub struct Parsed(Vec<Card>);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    Ok(parsed
+        .0
+        .iter()
+        .filter(|card| card.matches > 0)
+        .map(|card| 2usize.pow(card.matches as u32 - 1))
+        .sum::<usize>())
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    Ok(cascade(&parsed.0)
+        .iter()
+        .map(|card| card.instances)
+        .sum::<usize>())
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+/// How many copies of each card were received and won, one line per card.
+pub fn trace(input: &str) -> Result<String, ParseError> {
+    let cards = parse(input)?;
+    let mut report = String::new();
+
+    for (card, trace) in cards.iter().zip(cascade(&cards)) {
+        report += &format!(
+            "card {}: {} matches, {} instances ({} copies received), {} copies won",
+            card.id,
+            card.matches,
+            trace.instances,
+            trace.instances - 1,
+            trace.won
+        );
+        if trace.overflow > 0 {
+            report += &format!(", {} copies past the last card", trace.overflow);
+        }
+        report.push('\n');
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn matching_numbers() {
+        let cards = parse(TEST_INPUT).unwrap();
+        assert_eq!(cards[0].matches, 4);
+        assert_eq!(cards[5].matches, 0);
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(13));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(30));
+    }
+
+    #[test]
+    fn cascade_trace() {
+        let cards = parse(TEST_INPUT).unwrap();
+        let trace = cascade(&cards);
+        assert_eq!(
+            trace[3],
+            CardTrace {
+                instances: 8,
+                won: 8,
+                overflow: 0
+            }
+        );
+        assert_eq!(
+            trace.iter().map(|card| card.won).sum::<usize>(),
+            30 - cards.len()
+        );
+    }
+
+    #[test]
+    fn copies_past_the_end() {
+        let input = "Card 1: 1 2 | 1 2\nCard 2: 3 4 5 | 3 4 5\n";
+        assert_eq!(part2(input), Ok(3));
+        assert_eq!(
+            trace(input),
+            Ok(
+                "card 1: 2 matches, 1 instances (0 copies received), 1 copies won, \
+                1 copies past the last card\n\
+                card 2: 3 matches, 2 instances (1 copies received), 0 copies won, \
+                6 copies past the last card\n"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn card_ids() {
+        assert_eq!(
+            part2("Card 1: 1 | 1\nCard 3: 2 | 2\n"),
+            Err(ParseError::new(2, 6, "3", "card 2").into())
+        );
     }
-    println!("part 2: {}", instances.iter().sum::<usize>());
 }
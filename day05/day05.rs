@@ -1,177 +1,649 @@
-use std::collections::HashMap;
-use std::io;
+use common::{parse_token, Error, ParseError};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 
-fn parse_seeds(s: &str) -> Vec<u64> {
-    s.strip_prefix("seeds:")
-        .expect("seeds not found")
+// The seeds, and the same numbers read in pairs as seed ranges. Only part 2
+// reads the seeds as ranges, so these are checked without failing the seeds.
+fn parse_seeds(input: &str, s: &str) -> Result<(Vec<u64>, SeedRanges), ParseError> {
+    let tokens: Vec<&str> = s
+        .strip_prefix("seeds:")
+        .ok_or_else(|| ParseError::at(input, s, "\"seeds: <seeds>\""))?
         .split_whitespace()
-        .filter_map(|n| n.parse::<u64>().ok())
-        .collect::<Vec<u64>>()
-}
-
-fn read_maps() -> HashMap<String, Vec<MapRange>> {
-    let mut maps: HashMap<String, Vec<MapRange>> = HashMap::new();
-    let stdin = io::stdin();
-
-    loop {
-        let mut line = String::new();
-        match stdin.read_line(&mut line) {
-            Err(_) => panic!("error parsing input"),
-            Ok(0) => break,
-            Ok(1) => continue,
-            Ok(_) => {
-                let map_name = line[..line.find(' ').unwrap()].to_string();
-                let mut map_ranges = Vec::new();
-
-                loop {
-                    let mut line = String::new();
-                    match stdin.read_line(&mut line) {
-                        Err(_) => panic!("error parsing input"),
-                        Ok(0) | Ok(1) => break,
-                        Ok(_) => {
-                            let values: Vec<u64> = line
-                                .split_whitespace()
-                                .map(|s| s.parse::<u64>().unwrap())
-                                .collect();
-                            let dst = values[0];
-                            let start = values[1];
-                            let end = start + values[2] - 1;
-                            map_ranges.push(MapRange {
-                                r: Range { start, end },
-                                dst,
-                            });
-                        }
-                    }
-                }
+        .collect();
+    let seeds = tokens
+        .iter()
+        .map(|n| parse_token(input, n, "a seed number"))
+        .collect::<Result<Vec<u64>, ParseError>>()?;
+    if seeds.is_empty() {
+        return Err(ParseError::at(input, &s[s.len()..], "a seed number"));
+    }
+
+    let ranges = seed_ranges(input, s, &seeds, &tokens);
+
+    Ok((seeds, ranges))
+}
+
+fn seed_ranges(input: &str, s: &str, seeds: &[u64], tokens: &[&str]) -> SeedRanges {
+    if seeds.len() % 2 == 1 {
+        return Err(ParseError::at(input, &s[s.len()..], "a seed range length"));
+    }
 
-                map_ranges.sort_by_key(|mr| mr.r.start);
-                maps.insert(map_name, map_ranges);
+    seeds
+        .chunks(2)
+        .zip(tokens.chunks(2))
+        .map(|(pair, tokens)| {
+            let (&[start, len], &[start_token, len_token]) = (pair, tokens) else {
+                unreachable!()
+            };
+            let last = len
+                .checked_sub(1)
+                .ok_or_else(|| ParseError::at(input, len_token, "a non-zero seed range length"))?;
+            let end = start.checked_add(last).ok_or_else(|| {
+                // blame both numbers of the pair
+                let offset = |token: &str| token.as_ptr() as usize - s.as_ptr() as usize;
+                let pair = &s[offset(start_token)..offset(len_token) + len_token.len()];
+                ParseError::at(input, pair, "seed ranges within 0 and 2^64 - 1")
+            })?;
+            Ok(Range { start, end })
+        })
+        .collect()
+}
+
+fn parse_piece(input: &str, line: &str) -> Result<Piece, ParseError> {
+    let values = line
+        .split_whitespace()
+        .map(|s| parse_token(input, s, "a number"))
+        .collect::<Result<Vec<u64>, ParseError>>()?;
+    let [dst, start, len] = values[..] else {
+        return Err(ParseError::at(
+            input,
+            line,
+            "\"<destination start> <source start> <length>\"",
+        ));
+    };
+
+    // both ranges must be non-empty and representable
+    let end = len
+        .checked_sub(1)
+        .filter(|&last| dst.checked_add(last).is_some())
+        .and_then(|last| start.checked_add(last))
+        .ok_or_else(|| ParseError::at(input, line, "ranges within 0 and 2^64 - 1"))?;
+
+    Ok(Piece {
+        src: Range { start, end },
+        dst,
+    })
+}
+
+fn read_maps<'a>(input: &str, s: &'a str) -> Result<HashMap<&'a str, Vec<Map<'a>>>, ParseError> {
+    let mut maps: HashMap<&str, Vec<Map>> = HashMap::new();
+
+    for block in s.split("\n\n") {
+        let mut lines = block.lines();
+        let Some(header) = lines.next() else {
+            continue;
+        };
+        let (source, destination) = header
+            .strip_suffix(" map:")
+            .and_then(|name| name.split_once("-to-"))
+            .filter(|(source, destination)| !source.is_empty() && !destination.is_empty())
+            .ok_or_else(|| ParseError::at(input, header, "\"<source>-to-<destination> map:\""))?;
+        let from_source: &mut Vec<Map> = maps.entry(source).or_default();
+        if from_source.iter().any(|map| map.destination == destination) {
+            return Err(ParseError::at(
+                input,
+                header,
+                "a map between categories not linked yet",
+            ));
+        }
+        let mut pieces = lines
+            .map(|line| Ok((parse_piece(input, line)?, line)))
+            .collect::<Result<Vec<(Piece, &str)>, ParseError>>()?;
+
+        pieces.sort_by_key(|(piece, _)| piece.src.start);
+        for pair in pieces.windows(2) {
+            let [(previous, previous_line), (piece, line)] = pair else {
+                unreachable!()
+            };
+            if piece.src.start <= previous.src.end {
+                // blame the range listed last
+                let line = std::cmp::max_by_key(*line, *previous_line, |line| line.as_ptr());
+                return Err(ParseError::at(
+                    input,
+                    line,
+                    "a source range not overlapping the others",
+                ));
             }
         }
+
+        let pieces = pieces.into_iter().map(|(piece, _)| piece).collect();
+        from_source.push(Map {
+            source,
+            destination,
+            map: PiecewiseMap::new(pieces),
+        });
     }
 
-    maps
+    Ok(maps)
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 struct Range {
     start: u64,
     end: u64,
 }
 
-struct MapRange {
-    r: Range,
+impl Range {
+    fn intersection(self, other: Range) -> Option<Range> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        (start <= end).then_some(Range { start, end })
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+// Maps `src` onto the range of the same length starting at `dst`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Piece {
+    src: Range,
     dst: u64,
 }
 
-fn map_ranges(srcs: &[Range], map: &[MapRange]) -> Vec<Range> {
-    let mut dsts: Vec<Range> = srcs
-        .iter()
-        .map(|src| map_range(src, map).into_iter())
-        .flatten()
-        .collect();
-    dsts.sort_by_key(|r| r.start);
-
-    // merge ranges
-    let mut idx = 0;
-    for i in 1..dsts.len() {
-        if dsts[i].start <= dsts[idx].end {
-            dsts[idx].end = dsts[idx].end.max(dsts[i].end);
-        } else {
-            idx += 1;
-            dsts[idx] = dsts[i];
+impl Piece {
+    fn apply(&self, x: u64) -> u64 {
+        self.dst + (x - self.src.start)
+    }
+
+    fn image(&self) -> Range {
+        Range {
+            start: self.dst,
+            end: self.apply(self.src.end),
         }
     }
+}
 
-    dsts[..=idx].to_vec()
+// Map defined on every u64 by pieces sorted by source, values outside the
+// given pieces mapping to themselves.
+#[derive(Debug, PartialEq, Eq)]
+struct PiecewiseMap {
+    pieces: Vec<Piece>,
 }
 
-fn map_range(src: &Range, map: &[MapRange]) -> Vec<Range> {
-    let mut mapped = Vec::new();
-    let mut idx = match map.binary_search_by_key(&src.start, |r| r.r.start) {
-        Ok(i) => i,
-        Err(i) => i,
-    };
-    if idx > 0 && src.start <= map[idx - 1].r.end {
-        idx = idx - 1;
+impl PiecewiseMap {
+    // `pieces` are sorted and don't overlap
+    fn new(pieces: Vec<Piece>) -> Self {
+        let mut filled = Vec::new();
+        // first value not covered yet, none once the last u64 is
+        let mut next = Some(0);
+
+        for piece in pieces {
+            let uncovered = next.expect("overlapping pieces");
+            if piece.src.start > uncovered {
+                filled.push(Piece {
+                    src: Range {
+                        start: uncovered,
+                        end: piece.src.start - 1,
+                    },
+                    dst: uncovered,
+                });
+            }
+            filled.push(piece);
+            next = piece.src.end.checked_add(1);
+        }
+        if let Some(uncovered) = next {
+            filled.push(Piece {
+                src: Range {
+                    start: uncovered,
+                    end: u64::MAX,
+                },
+                dst: uncovered,
+            });
+        }
+
+        Self::merged(filled)
     }
 
-    let mut cur_start = src.start;
-    while cur_start <= src.end {
-        let cur_end;
+    fn identity() -> Self {
+        Self::new(Vec::new())
+    }
 
-        if idx < map.len() && map[idx].r.start <= cur_start {
-            cur_end = map[idx].r.end.min(src.end);
-            let mapped_start = map[idx].dst + (cur_start - map[idx].r.start);
-            let mapped_end = mapped_start + (cur_end - cur_start);
-            mapped.push(Range {
-                start: mapped_start,
-                end: mapped_end,
-            });
-            idx += 1;
-        } else {
-            if idx < map.len() {
-                cur_end = (map[idx].r.start - 1).min(src.end);
-            } else {
-                cur_end = src.end;
+    // joins the neighbouring pieces that continue each other
+    fn merged(pieces: Vec<Piece>) -> Self {
+        let mut merged: Vec<Piece> = Vec::with_capacity(pieces.len());
+        for piece in pieces {
+            match merged.last_mut() {
+                Some(last)
+                    if last.src.end + 1 == piece.src.start
+                        && last.image().end.checked_add(1) == Some(piece.dst) =>
+                {
+                    last.src.end = piece.src.end;
+                }
+                _ => merged.push(piece),
+            }
+        }
+
+        PiecewiseMap { pieces: merged }
+    }
+
+    // index of the piece containing `x`
+    fn find(&self, x: u64) -> usize {
+        self.pieces.partition_point(|piece| piece.src.end < x)
+    }
+
+    fn apply(&self, x: u64) -> u64 {
+        self.pieces[self.find(x)].apply(x)
+    }
+
+    // The pieces of `self` overlapping `r`, cut to `r`.
+    fn restricted(&self, r: Range) -> impl Iterator<Item = Piece> + '_ {
+        self.pieces[self.find(r.start)..]
+            .iter()
+            .map_while(move |piece| {
+                let src = piece.src.intersection(r)?;
+                Some(Piece {
+                    src,
+                    dst: piece.apply(src.start),
+                })
+            })
+    }
+
+    // the map applying `self` then `next`
+    fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut pieces = Vec::new();
+        for piece in &self.pieces {
+            for next_piece in next.restricted(piece.image()) {
+                let start = piece.src.start + (next_piece.src.start - piece.dst);
+                pieces.push(Piece {
+                    src: Range {
+                        start,
+                        end: start + (next_piece.src.end - next_piece.src.start),
+                    },
+                    dst: next_piece.dst,
+                });
             }
-            let mapped_start = cur_start;
-            let mapped_end = cur_end;
-            mapped.push(Range {
-                start: mapped_start,
-                end: mapped_end,
-            });
         }
 
-        cur_start = cur_end + 1;
+        Self::merged(pieces)
+    }
+
+    // Lowest value `r` is mapped to, each piece being increasing. Ranges are
+    // never empty and the map is total, so `r` meets at least one piece.
+    fn min_image(&self, r: Range) -> u64 {
+        self.restricted(r).map(|piece| piece.dst).min().unwrap()
     }
 
-    mapped
+    // The values mapped into `window`, as sorted disjoint ranges.
+    fn preimage(&self, window: Range) -> Vec<Range> {
+        let mut ranges: Vec<Range> = self
+            .pieces
+            .iter()
+            .filter_map(|piece| {
+                let image = piece.image().intersection(window)?;
+                Some(Range {
+                    start: piece.src.start + (image.start - piece.dst),
+                    end: piece.src.start + (image.end - piece.dst),
+                })
+            })
+            .collect();
+
+        // pieces are sorted by source, so only neighbours can touch
+        let mut merged: Vec<Range> = Vec::with_capacity(ranges.len());
+        for r in ranges.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.end + 1 == r.start => last.end = r.end,
+                _ => merged.push(r),
+            }
+        }
+        merged
+    }
 }
 
-fn lowest_location(seeds: &[Range], maps: &HashMap<String, Vec<MapRange>>) -> u64 {
-    let map_order = [
-        "seed-to-soil",
-        "soil-to-fertilizer",
-        "fertilizer-to-water",
-        "water-to-light",
-        "light-to-temperature",
-        "temperature-to-humidity",
-        "humidity-to-location",
-    ];
+impl fmt::Display for PiecewiseMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for piece in &self.pieces {
+            writeln!(f, "{} -> {}", piece.src, piece.image())?;
+        }
+        Ok(())
+    }
+}
 
-    let mut tmp = seeds.to_vec();
+// Map from the categories in `header` ("<source>-to-<destination> map:").
+struct Map<'a> {
+    source: &'a str,
+    destination: &'a str,
+    map: PiecewiseMap,
+}
 
-    for map_name in map_order {
-        tmp = map_ranges(&tmp, maps.get(map_name).unwrap());
+// the seed ranges, or why the seeds can't be read as such
+type SeedRanges = Result<Vec<Range>, ParseError>;
+
+struct Almanac<'a> {
+    seeds: Vec<u64>,
+    seed_ranges: SeedRanges,
+    // maps by source category, in the order they're listed
+    maps: HashMap<&'a str, Vec<Map<'a>>>,
+}
+
+// The maps leading from seed to a category reached by the search in `seed_to`.
+fn chain<'a>(
+    reached: &HashMap<&str, Option<&'a Map<'a>>>,
+    mut category: &'a str,
+) -> Vec<&'a Map<'a>> {
+    let mut chain = Vec::new();
+    while let Some(map) = reached[category] {
+        chain.push(map);
+        category = map.source;
+    }
+    chain.reverse();
+    chain
+}
+
+impl Almanac<'_> {
+    // The map from seeds to `target`, composed from the shortest chain of maps
+    // leading there from the seed category.
+    fn seed_to(&self, target: &str) -> Result<PiecewiseMap, Error> {
+        // breadth-first through the categories, each one reached by a map
+        let mut reached: HashMap<&str, Option<&Map>> = HashMap::from([("seed", None)]);
+        let mut queue = VecDeque::from(["seed"]);
+        let mut dead_end = None;
+
+        while let Some(category) = queue.pop_front() {
+            if category == target {
+                return Ok(chain(&reached, category)
+                    .iter()
+                    .fold(PiecewiseMap::identity(), |composed, map| {
+                        composed.then(&map.map)
+                    }));
+            }
+
+            let maps = self.maps.get(category).map_or(&[][..], Vec::as_slice);
+            if maps.is_empty() {
+                dead_end.get_or_insert(category);
+            }
+            for map in maps {
+                if !reached.contains_key(map.destination) {
+                    reached.insert(map.destination, Some(map));
+                    queue.push_back(map.destination);
+                }
+            }
+        }
+
+        let mut categories = vec!["seed"];
+        if let Some(category) = dead_end {
+            categories.extend(chain(&reached, category).iter().map(|map| map.destination));
+            return Err(Error::NoSolution(format!(
+                "the maps from {} stop before reaching {}",
+                categories.join(" to "),
+                target
+            )));
+        }
+        // every category reached has maps, so the first ones go round in circles
+        loop {
+            let map = &self.maps[categories[categories.len() - 1]][0];
+            let looped = categories.contains(&map.destination);
+            categories.push(map.destination);
+            if looped {
+                return Err(Error::NoSolution(format!(
+                    "the maps from {} go round in circles before reaching {}",
+                    categories.join(" to "),
+                    target
+                )));
+            }
+        }
     }
+}
+
+fn parse(input: &str) -> Result<Almanac<'_>, ParseError> {
+    let (seeds, maps) = input
+        .split_once("\n\n")
+        .ok_or_else(|| ParseError::end_of(input, "a blank line after the seeds"))?;
+
+    let (seeds, seed_ranges) = parse_seeds(input, seeds)?;
 
-    tmp.iter().map(|r| r.start).min().unwrap()
+    Ok(Almanac {
+        seeds,
+        seed_ranges,
+        maps: read_maps(input, maps)?,
+    })
 }
 
-fn main() {
-    let mut line = String::new();
-    io::stdin().read_line(&mut line).unwrap();
-    let seeds = parse_seeds(&line);
-    let maps = read_maps();
+/// The almanac, with its maps sorted out by source category.
+pub struct Parsed<'a>(Almanac<'a>);
 
-    let single_seed_ranges: Vec<Range> = seeds
+pub fn parse_input(input: &str) -> Result<Parsed<'_>, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<u64, Error> {
+    let almanac = &parsed.0;
+    let map = almanac.seed_to("location")?;
+
+    Ok(almanac
+        .seeds
         .iter()
-        .map(|&seed| Range {
-            start: seed,
-            end: seed,
-        })
-        .collect();
-    println!("part 1: {}", lowest_location(&single_seed_ranges, &maps));
+        .map(|&seed| map.apply(seed))
+        .min()
+        .unwrap())
+}
 
-    let seed_ranges: Vec<Range> = seeds
+pub fn solve_part2(parsed: &Parsed) -> Result<u64, Error> {
+    let almanac = &parsed.0;
+    let map = almanac.seed_to("location")?;
+
+    Ok(almanac
+        .seed_ranges
+        .clone()?
         .iter()
-        .step_by(2)
-        .zip(seeds.iter().skip(1).step_by(2))
-        .map(|(&seed1, &seed2)| Range {
-            start: seed1,
-            end: seed1 + seed2 - 1,
-        })
+        .map(|&r| map.min_image(r))
+        .min()
+        .unwrap())
+}
+
+pub fn part1(input: &str) -> Result<u64, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<u64, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+/// The map from seeds to the `target` category, one "seeds -> targets" line
+/// per piece.
+pub fn composed_map(input: &str, target: &str) -> Result<String, Error> {
+    let almanac = parse(input)?;
+
+    Ok(almanac.seed_to(target)?.to_string())
+}
+
+/// The seed ranges landing in `start` to `end` of the `target` category,
+/// followed by the almanac's seeds among them.
+pub fn seeds_for(input: &str, target: &str, start: u64, end: u64) -> Result<String, Error> {
+    let almanac = parse(input)?;
+    let seeds = &almanac.seeds;
+    let ranges = almanac.seed_to(target)?.preimage(Range { start, end });
+
+    let mut report: String = ranges.iter().map(|r| format!("{}\n", r)).collect();
+    let listed: Vec<String> = seeds
+        .iter()
+        .filter(|&&seed| ranges.iter().any(|r| (r.start..=r.end).contains(&seed)))
+        .map(|seed| seed.to_string())
         .collect();
-    println!("part 2: {}", lowest_location(&seed_ranges, &maps));
+    report += &format!("seeds: {}\n", listed.join(", "));
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(35));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(46));
+    }
+
+    #[test]
+    fn composition() {
+        let map = parse(TEST_INPUT).unwrap().seed_to("location").unwrap();
+        for (seed, location) in [(79, 82), (14, 43), (55, 86), (13, 35), (82, 46)] {
+            assert_eq!(map.apply(seed), location);
+        }
+        assert!(map
+            .pieces
+            .windows(2)
+            .all(|pair| pair[0].src.end + 1 == pair[1].src.start));
+        assert_eq!(map.pieces.last().unwrap().src.end, u64::MAX);
+    }
+
+    #[test]
+    fn inverse() {
+        let map = parse(TEST_INPUT).unwrap().seed_to("location").unwrap();
+        let window = Range { start: 40, end: 60 };
+        let preimage = map.preimage(window);
+
+        for seed in 0..200 {
+            let in_preimage = preimage.iter().any(|r| (r.start..=r.end).contains(&seed));
+            let location = map.apply(seed);
+            assert_eq!(in_preimage, (window.start..=window.end).contains(&location));
+        }
+        assert_eq!(
+            seeds_for(TEST_INPUT, "location", 46, 46),
+            Ok("82-82\nseeds: \n".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        let input = "seeds: 1 1\n\nseed-to-soil map:\n50 98 0\n";
+        assert_eq!(
+            part1(input),
+            Err(ParseError::new(4, 1, "50 98 0", "ranges within 0 and 2^64 - 1").into())
+        );
+        let input = "seeds: 1 1\n\nseed-to-soil map:\n50 98 2\n0 90 9\n";
+        assert_eq!(
+            part1(input),
+            Err(
+                ParseError::new(5, 1, "0 90 9", "a source range not overlapping the others").into()
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_seed_ranges() {
+        let input = "seeds: 0 0\n\nseed-to-location map:\n";
+        assert_eq!(
+            part2(input),
+            Err(ParseError::new(1, 10, "0", "a non-zero seed range length").into())
+        );
+        let input = "seeds: 18446744073709551615 5\n\nseed-to-location map:\n";
+        assert_eq!(
+            part2(input),
+            Err(ParseError::new(
+                1,
+                8,
+                "18446744073709551615 5",
+                "seed ranges within 0 and 2^64 - 1"
+            )
+            .into())
+        );
+        let input = "seeds: 79 14 55\n\nseed-to-location map:\n";
+        assert_eq!(
+            part2(input),
+            Err(ParseError::new(1, 16, "", "a seed range length").into())
+        );
+        assert_eq!(part1(input), Ok(14));
+        assert_eq!(part1("seeds: 0 0\n\nseed-to-location map:\n"), Ok(0));
+        let input = "seeds: 18446744073709551615 1\n\nseed-to-location map:\n";
+        assert_eq!(part2(input), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn map_chain() {
+        let input = "seeds: 1 7\n\n\
+                     soil-to-rock map:\n0 6 1\n\n\
+                     seed-to-soil map:\n5 0 3\n";
+        let almanac = parse(input).unwrap();
+        assert_eq!(almanac.seed_to("rock").unwrap().apply(1), 0);
+        assert_eq!(almanac.seed_to("seed").unwrap().apply(1), 1);
+        assert_eq!(
+            composed_map(input, "rock"),
+            Ok(
+                "0-0 -> 5-5\n1-1 -> 0-0\n2-2 -> 7-7\n3-5 -> 3-5\n6-6 -> 0-0\n\
+                7-18446744073709551615 -> 7-18446744073709551615\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            seeds_for(input, "rock", 0, 0),
+            Ok("1-1\n6-6\nseeds: 1\n".to_string())
+        );
+        assert_eq!(
+            part1(input),
+            Err(Error::no_solution(
+                "the maps from seed to soil to rock stop before reaching location"
+            ))
+        );
+    }
+
+    #[test]
+    fn category_graph() {
+        let input = "seeds: 1 7\n\n\
+                     seed-to-water map:\n10 0 5\n\n\
+                     seed-to-soil map:\n5 0 3\n\n\
+                     soil-to-rock map:\n0 6 1\n\n\
+                     water-to-sand map:\n\n\
+                     rock-to-location map:\n100 0 1\n";
+        let almanac = parse(input).unwrap();
+        assert_eq!(almanac.seed_to("rock").unwrap().apply(1), 0);
+        assert_eq!(almanac.seed_to("sand").unwrap().apply(1), 11);
+        assert_eq!(part1(input), Ok(7));
+        assert_eq!(
+            almanac.seed_to("gravel").map(|_| ()),
+            Err(Error::no_solution(
+                "the maps from seed to water to sand stop before reaching gravel"
+            ))
+        );
+    }
+
+    #[test]
+    fn invalid_chains() {
+        let input = "seeds: 1 1\n\nseed-to-soil map:\n\nsoil-to-seed map:\n";
+        assert_eq!(
+            part1(input),
+            Err(Error::no_solution(
+                "the maps from seed to soil to seed go round in circles before reaching location"
+            ))
+        );
+        let input = "seeds: 1 1\n\nseed-to-soil map:\n\nseed-to-soil map:\n";
+        assert_eq!(
+            part1(input),
+            Err(ParseError::new(
+                5,
+                1,
+                "seed-to-soil map:",
+                "a map between categories not linked yet"
+            )
+            .into())
+        );
+        let input = "seeds: 1 1\n\nseed-soil map:\n";
+        assert_eq!(
+            part1(input),
+            Err(
+                ParseError::new(3, 1, "seed-soil map:", "\"<source>-to-<destination> map:\"")
+                    .into()
+            )
+        );
+    }
 }
@@ -1,56 +1,177 @@
-use std::io;
+use common::{parse_token, Error, ParseError};
+use std::fmt::Display;
+use std::str::FromStr;
 
-fn read_input(p: &str) -> Vec<u64> {
-    let mut line = String::new();
-    io::stdin()
-        .read_line(&mut line)
-        .expect("error reading input");
+fn read_input<'a, T: FromStr>(
+    input: &str,
+    line: Option<&'a str>,
+    p: &str,
+) -> Result<(&'a str, Vec<T>), ParseError> {
+    let expected = format!("\"{}: <values>\"", p);
+    let line = line.ok_or_else(|| ParseError::end_of(input, &expected))?;
 
-    line.strip_prefix(&(p.to_owned() + ":"))
-        .expect("error parsing input")
+    let values = line
+        .strip_prefix(&(p.to_owned() + ":"))
+        .ok_or_else(|| ParseError::at(input, line, &expected))?
         .split_whitespace()
-        .filter_map(|n| n.parse::<u64>().ok())
-        .collect()
+        .map(|n| parse_token(input, n, "a number"))
+        .collect::<Result<Vec<T>, ParseError>>()?;
+
+    Ok((line, values))
 }
 
-fn ways_to_beat_record(time: u64, record: u64) -> usize {
-    let delta = (time * time) - (4 * record);
-    let sqrt_delta = (delta as f64).sqrt();
-    let mut x1 = ((-(time as f64) - sqrt_delta) / -2 as f64).floor() as u64;
-    let mut x2 = ((-(time as f64) + sqrt_delta) / -2 as f64).ceil() as u64;
-    if x1 * (time - x1) <= record {
-        x1 = x1 - 1;
+// Number of ways to hold the button for `x` ms so that `x * (time - x)`, the
+// distance travelled, beats `record`. Exact for any race: times below 2^64 keep
+// `time^2` within a u128.
+fn ways_to_beat_record(time: u64, record: u128) -> u128 {
+    let time = time as u128;
+    let distance = |x: u128| x * (time - x);
+
+    // the best is to hold the button for half the race
+    if distance(time / 2) <= record {
+        return 0;
     }
-    if x2 * (time - x2) <= record {
-        x2 = x2 + 1;
+
+    // The winning holds are between the roots of x^2 - time * x + record, which
+    // are symmetric around time / 2, and the integer square root of the
+    // discriminant puts the first one within a step of the smallest winner.
+    let sqrt_delta = (time * time - 4 * record).isqrt();
+    let mut first = (time - sqrt_delta) / 2;
+    while first > 0 && distance(first - 1) > record {
+        first -= 1;
+    }
+    while distance(first) <= record {
+        first += 1;
     }
 
-    (x1 - x2 + 1) as usize
+    time - 2 * first + 1
 }
 
-fn apply_kerning(data: &[u64]) -> u64 {
+// The number written by all of `data` side by side, if it fits in a `T`.
+fn apply_kerning<T: Display + FromStr>(data: &[T]) -> Option<T> {
     data.iter()
         .map(|n| n.to_string())
-        .reduce(|acc, n| acc + &n)
-        .unwrap()
-        .parse::<u64>()
-        .unwrap()
-}
-
-fn main() {
-    let times = read_input("Time");
-    let distances = read_input("Distance");
-    println!(
-        "part 1: {}",
-        times
-            .iter()
-            .zip(&distances)
-            .map(|(&time, &distance)| ways_to_beat_record(time, distance))
-            .reduce(|acc, n| acc * n)
-            .unwrap()
-    );
-
-    let time = apply_kerning(&times);
-    let distance = apply_kerning(&distances);
-    println!("part 2: {}", ways_to_beat_record(time, distance));
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+struct Races {
+    times: Vec<u64>,
+    // the records to beat
+    distances: Vec<u128>,
+}
+
+fn parse(input: &str) -> Result<Races, ParseError> {
+    let mut lines = input.lines();
+    let (_, times) = read_input(input, lines.next(), "Time")?;
+    let (distance_line, distances) = read_input(input, lines.next(), "Distance")?;
+
+    if times.len() != distances.len() {
+        let expected = format!("{} distances", times.len());
+        return Err(ParseError::at(input, distance_line, &expected));
+    }
+
+    Ok(Races { times, distances })
+}
+
+/// The times of the races and the records to beat.
+pub struct Parsed(Races);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<u128, Error> {
+    let races = &parsed.0;
+
+    Ok(races
+        .times
+        .iter()
+        .zip(&races.distances)
+        .map(|(&time, &distance)| ways_to_beat_record(time, distance))
+        .product())
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<u128, Error> {
+    let races = &parsed.0;
+
+    let time = apply_kerning(&races.times)
+        .ok_or_else(|| Error::no_solution("the kerned time doesn't fit in 64 bits"))?;
+    let distance = apply_kerning(&races.distances)
+        .ok_or_else(|| Error::no_solution("the kerned distance doesn't fit in 128 bits"))?;
+    Ok(ways_to_beat_record(time, distance))
+}
+
+pub fn part1(input: &str) -> Result<u128, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<u128, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn ways() {
+        assert_eq!(ways_to_beat_record(7, 9), 4);
+        assert_eq!(ways_to_beat_record(15, 40), 8);
+        assert_eq!(ways_to_beat_record(30, 200), 9);
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(288));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(71503));
+    }
+
+    #[test]
+    fn unbeatable_records() {
+        assert_eq!(ways_to_beat_record(0, 0), 0);
+        assert_eq!(ways_to_beat_record(7, 12), 0);
+        assert_eq!(ways_to_beat_record(7, 11), 2);
+        assert_eq!(ways_to_beat_record(8, 16), 0);
+        assert_eq!(ways_to_beat_record(8, 15), 1);
+        assert_eq!(ways_to_beat_record(10, u128::MAX), 0);
+    }
+
+    #[test]
+    fn long_races() {
+        let time = u64::MAX;
+        let half = time as u128 / 2;
+        let best = half * (half + 1);
+        assert_eq!(ways_to_beat_record(time, best), 0);
+        assert_eq!(ways_to_beat_record(time, best - 1), 2);
+        assert_eq!(ways_to_beat_record(time, 0), time as u128 - 1);
+
+        // matches counting the winners one by one
+        for time in 0..60 {
+            for record in 0..1000 {
+                let winners = (0..=time as u128)
+                    .filter(|x| x * (time as u128 - x) > record)
+                    .count();
+                assert_eq!(ways_to_beat_record(time, record), winners as u128);
+            }
+        }
+    }
+
+    #[test]
+    fn kerning_overflow() {
+        let input = "Time: 18446744073 709551616\nDistance: 1 2\n";
+        assert_eq!(
+            part2(input),
+            Err(Error::no_solution("the kerned time doesn't fit in 64 bits"))
+        );
+        let input = "Time: 1844674407 3709551615\nDistance: 1 2\n";
+        assert_eq!(part2(input), Ok(u64::MAX as u128 - 1));
+    }
 }
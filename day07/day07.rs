@@ -1,49 +1,9 @@
-use std::cmp::Ordering;
+use common::{parse_token, Error, ParseError};
 use std::collections::HashMap;
-use std::convert::TryInto;
-use std::io;
 use std::str::FromStr;
+use std::sync::LazyLock;
 
-#[derive(Debug, Hash, Ord, PartialOrd, Eq, PartialEq, Clone, Copy)]
-enum Card {
-    Joker,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Jack,
-    Queen,
-    King,
-    Ace,
-}
-
-impl Card {
-    fn new(c: u8) -> Option<Self> {
-        match c {
-            b'A' => Some(Card::Ace),
-            b'K' => Some(Card::King),
-            b'Q' => Some(Card::Queen),
-            b'J' => Some(Card::Jack),
-            b'T' => Some(Card::Ten),
-            b'9' => Some(Card::Nine),
-            b'8' => Some(Card::Eight),
-            b'7' => Some(Card::Seven),
-            b'6' => Some(Card::Six),
-            b'5' => Some(Card::Five),
-            b'4' => Some(Card::Four),
-            b'3' => Some(Card::Three),
-            b'2' => Some(Card::Two),
-            _ => None,
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
 enum HandType {
     HighCard,
     OnePair,
@@ -54,43 +14,51 @@ enum HandType {
     FiveOAK,
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Hand {
-    cards: [Card; 5],
-}
+impl HandType {
+    const ALL: [HandType; 7] = [
+        HandType::HighCard,
+        HandType::OnePair,
+        HandType::TwoPair,
+        HandType::ThreeOAK,
+        HandType::FullHouse,
+        HandType::FourOAK,
+        HandType::FiveOAK,
+    ];
 
-impl Hand {
-    fn new(cards: &[Card; 5]) -> Self {
-        let mut cloned: [Card; 5] = [Card::Ace; 5];
-        cloned.clone_from_slice(cards);
-        Hand { cards: cloned }
+    fn name(&self) -> &'static str {
+        match self {
+            HandType::HighCard => "high-card",
+            HandType::OnePair => "one-pair",
+            HandType::TwoPair => "two-pair",
+            HandType::ThreeOAK => "three-of-a-kind",
+            HandType::FullHouse => "full-house",
+            HandType::FourOAK => "four-of-a-kind",
+            HandType::FiveOAK => "five-of-a-kind",
+        }
     }
 
-    fn hand_type(&self) -> HandType {
-        let mut card_counter: HashMap<Card, usize> = HashMap::new();
-        for card in self.cards {
-            card_counter
-                .entry(card)
-                .and_modify(|count| *count += 1)
-                .or_insert(1);
-        }
+    fn from_name(name: &str) -> Option<Self> {
+        HandType::ALL.into_iter().find(|t| t.name() == name)
+    }
 
-        if card_counter.contains_key(&Card::Joker) {
-            let joker_count = *card_counter.get(&Card::Joker).unwrap();
-            card_counter.remove(&Card::Joker);
-            let max_count_card = *card_counter
-                .iter()
-                .max_by_key(|(_, count)| *count)
-                .map(|(card, _)| card)
-                .unwrap_or(&Card::Joker);
-            card_counter
-                .entry(max_count_card)
-                .and_modify(|count| *count += joker_count)
-                .or_insert(joker_count);
+    fn new(cards: &str, wild: Option<char>) -> Self {
+        let mut card_counter: HashMap<char, usize> = HashMap::new();
+        let mut wild_count = 0;
+        for card in cards.chars() {
+            if Some(card) == wild {
+                wild_count += 1;
+            } else {
+                *card_counter.entry(card).or_default() += 1;
+            }
         }
 
-        let mut counts: Vec<usize> = card_counter.iter().map(|(_, &count)| count).collect();
+        let mut counts: Vec<usize> = card_counter.into_values().collect();
         counts.sort();
+        // wild cards join the largest group
+        match counts.last_mut() {
+            Some(largest) => *largest += wild_count,
+            None => counts.push(wild_count),
+        }
 
         match counts.as_slice() {
             [5] => HandType::FiveOAK,
@@ -105,82 +73,367 @@ impl Hand {
     }
 }
 
-#[derive(Debug)]
-struct HandParseError;
+/// How Camel Cards hands are ranked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    name: String,
+    // card labels from the weakest to the strongest
+    ranking: Vec<char>,
+    // card counting as whichever card makes the strongest hand
+    wild: Option<char>,
+    // hand types from the weakest to the strongest
+    precedence: Vec<HandType>,
+}
 
-impl FromStr for Hand {
-    type Err = HandParseError;
+impl Rules {
+    fn card_rank(&self, card: char) -> Option<usize> {
+        self.ranking.iter().position(|&c| c == card)
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let bytes = s.as_bytes();
-        if bytes.len() != 5 {
-            return Err(HandParseError);
+    fn type_rank(&self, hand_type: HandType) -> usize {
+        self.precedence
+            .iter()
+            .position(|&t| t == hand_type)
+            .unwrap()
+    }
+
+    // `previous` are the sets read before, whose names can't be reused
+    fn parse(input: &str, block: &str, previous: &[Rules]) -> Result<Self, ParseError> {
+        let mut name = None;
+        let mut ranking = None;
+        let mut wild = None;
+        let mut precedence = None;
+        let mut keys = Vec::new();
+
+        for line in block.lines().filter(|line| !line.starts_with('#')) {
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| ParseError::at(input, line, "\"<key>: <value>\""))?;
+            let value = value.trim();
+
+            if keys.contains(&key) {
+                return Err(ParseError::at(input, key, "a key not given yet"));
+            }
+            keys.push(key);
+            match key {
+                "name" if previous.iter().any(|rules| rules.name == value) => {
+                    return Err(ParseError::at(
+                        input,
+                        value,
+                        "a name not given to another rule set",
+                    ))
+                }
+                "name" if value.is_empty() => {
+                    return Err(ParseError::at(input, value, "a non-empty name"))
+                }
+                "name" => name = Some(value.to_string()),
+                "ranking" => {
+                    let cards: Vec<char> = value.chars().collect();
+                    if cards.is_empty() {
+                        return Err(ParseError::at(input, value, "card labels"));
+                    }
+                    for (i, c) in value.char_indices() {
+                        if !c.is_ascii_alphanumeric() || value[..i].contains(c) {
+                            let card = &value[i..i + c.len_utf8()];
+                            return Err(ParseError::at(input, card, "a card not ranked yet"));
+                        }
+                    }
+                    ranking = Some(cards);
+                }
+                "wild" => {
+                    let mut chars = value.chars();
+                    match (chars.next(), chars.next()) {
+                        (None, _) => {}
+                        (Some(c), None) => wild = Some((c, value)),
+                        _ => return Err(ParseError::at(input, value, "a single card")),
+                    }
+                }
+                "precedence" => {
+                    let hand_types = value
+                        .split_whitespace()
+                        .map(|name| {
+                            HandType::from_name(name)
+                                .ok_or_else(|| ParseError::at(input, name, "a hand type"))
+                        })
+                        .collect::<Result<Vec<HandType>, ParseError>>()?;
+                    if let Some(missing) = HandType::ALL.iter().find(|t| !hand_types.contains(t)) {
+                        let expected = format!("every hand type, {} included", missing.name());
+                        return Err(ParseError::at(input, value, &expected));
+                    }
+                    if hand_types.len() != HandType::ALL.len() {
+                        return Err(ParseError::at(input, value, "each hand type once"));
+                    }
+                    precedence = Some(hand_types);
+                }
+                _ => {
+                    return Err(ParseError::at(
+                        input,
+                        line,
+                        "name, ranking, wild or precedence",
+                    ))
+                }
+            }
         }
 
-        let cards: [Card; 5] = bytes
-            .iter()
-            .filter_map(|&b| Card::new(b))
-            .collect::<Vec<Card>>()
-            .try_into()
-            .or(Err(HandParseError))?;
-        Ok(Hand::new(&cards))
+        let name = name.ok_or_else(|| ParseError::at(input, block, "a name"))?;
+        let ranking = ranking.ok_or_else(|| ParseError::at(input, block, "a ranking"))?;
+        let wild = match wild {
+            Some((c, _)) if ranking.contains(&c) => Some(c),
+            Some((_, value)) => return Err(ParseError::at(input, value, "a ranked card")),
+            None => None,
+        };
+
+        Ok(Rules {
+            name,
+            ranking,
+            wild,
+            precedence: precedence.unwrap_or(HandType::ALL.to_vec()),
+        })
     }
 }
 
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match self.hand_type().cmp(&other.hand_type()) {
-            Ordering::Equal => self.cards.cmp(&other.cards),
-            ord => ord,
-        }
+/// Rule sets read from "<key>: <value>" lines, each set being separated from
+/// the next by a blank line. A set has a `name`, a card `ranking` from the
+/// weakest card to the strongest, an optional `wild` card and an optional
+/// `precedence` of hand types from the weakest to the strongest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSets {
+    sets: Vec<Rules>,
+}
+
+// the rules of both parts, read once
+static BUILT_IN: LazyLock<RuleSets> = LazyLock::new(|| {
+    include_str!("rules")
+        .parse()
+        .expect("invalid built-in rules")
+});
+
+impl RuleSets {
+    fn get(&self, name: &str) -> Option<&Rules> {
+        self.sets.iter().find(|rules| rules.name == name)
     }
 }
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl Default for RuleSets {
+    fn default() -> Self {
+        BUILT_IN.clone()
     }
 }
 
-impl PartialEq for Hand {
-    fn eq(&self, other: &Self) -> bool {
-        match self.cmp(other) {
-            Ordering::Equal => true,
-            _ => false,
+impl FromStr for RuleSets {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sets = Vec::new();
+        for block in s
+            .split("\n\n")
+            .filter(|block| block.lines().any(|line| !line.starts_with('#')))
+        {
+            sets.push(Rules::parse(s, block, &sets)?);
+        }
+        if sets.is_empty() {
+            return Err(ParseError::end_of(s, "a rule set"));
         }
+
+        Ok(RuleSets { sets })
+    }
+}
+
+// The strength of the hand made of `cards` under `rules`: the rank of its type,
+// then the ranks of its cards in order.
+fn strength(cards: &str, rules: &Rules) -> Result<(usize, [usize; 5]), Error> {
+    let mut ranks = [0; 5];
+    for (c, rank) in cards.chars().zip(&mut ranks) {
+        *rank = rules.card_rank(c).ok_or_else(|| {
+            Error::NoSolution(format!("the {} rules don't rank card {}", rules.name, c))
+        })?;
     }
+
+    Ok((rules.type_rank(HandType::new(cards, rules.wild)), ranks))
 }
 
-impl Eq for Hand {}
+fn total_winnings(data: &[(&str, u64)], rules: &Rules) -> Result<u64, Error> {
+    let mut hands = data
+        .iter()
+        .map(|&(cards, bid)| Ok((strength(cards, rules)?, bid)))
+        .collect::<Result<Vec<_>, Error>>()?;
 
-fn total_winnings(data: &mut Vec<(Hand, u64)>) -> u64 {
-    data.sort_by_key(|(hand, _)| *hand);
-    data.iter()
+    hands.sort_by_key(|&(strength, _)| strength);
+    Ok(hands
+        .iter()
         .enumerate()
         .map(|(i, (_, bid))| (i + 1) as u64 * bid)
-        .sum::<u64>()
+        .sum::<u64>())
 }
 
-fn main() {
-    let mut data: Vec<(Hand, u64)> = io::stdin()
+// The hands, as 5 card labels, and their bids.
+fn parse(input: &str) -> Result<Vec<(&str, u64)>, ParseError> {
+    input
         .lines()
         .map(|line| {
-            let line = line.unwrap();
-            let hand: Hand = line[..5].parse().expect("error parsing hand");
-            let bid: u64 = line[6..].trim().parse().expect("error parsing bid");
+            let (hand, bid) = line
+                .split_once(' ')
+                .ok_or_else(|| ParseError::at(input, line, "\"<hand> <bid>\""))?;
+            if let Some((i, c)) = hand
+                .char_indices()
+                .find(|(_, c)| !c.is_ascii_alphanumeric())
+            {
+                return Err(ParseError::at(input, &hand[i..i + c.len_utf8()], "a card"));
+            }
+            if hand.len() != 5 {
+                return Err(ParseError::at(input, hand, "5 cards"));
+            }
+            let bid: u64 = parse_token(input, bid.trim(), "a bid")?;
 
-            (hand, bid)
+            Ok((hand, bid))
         })
-        .collect();
+        .collect()
+}
 
-    println!("part 1: {}", total_winnings(&mut data));
+/// The hands, as their card labels, and their bids.
+pub struct Parsed<'a>(Vec<(&'a str, u64)>);
 
-    for (hand, _) in &mut data {
-        for card in &mut hand.cards {
-            if card == &Card::Jack {
-                *card = Card::Joker;
-            }
-        }
+pub fn parse_input(input: &str) -> Result<Parsed<'_>, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<u64, Error> {
+    let rules = BUILT_IN
+        .get("standard")
+        .expect("no built-in standard rules");
+    total_winnings(&parsed.0, rules)
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<u64, Error> {
+    let rules = BUILT_IN.get("jokers").expect("no built-in jokers rules");
+    total_winnings(&parsed.0, rules)
+}
+
+pub fn part1(input: &str) -> Result<u64, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<u64, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+/// The total winnings under each rule set, one "<name>: <winnings>" line per set.
+pub fn winnings(input: &str, rule_sets: &RuleSets) -> Result<String, Error> {
+    let data = parse(input)?;
+
+    rule_sets
+        .sets
+        .iter()
+        .map(|rules| {
+            let winnings = total_winnings(&data, rules)?;
+            Ok(format!("{}: {}\n", rules.name, winnings))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn hand_types() {
+        assert_eq!(HandType::new("KK677", None), HandType::TwoPair);
+        assert_eq!(HandType::new("T55J5", None), HandType::ThreeOAK);
+        assert_eq!(HandType::new("T55J5", Some('J')), HandType::FourOAK);
+        assert_eq!(HandType::new("JJJJJ", Some('J')), HandType::FiveOAK);
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(6440));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(5905));
+    }
+
+    #[test]
+    fn rule_sets() {
+        let rules = "name: pairs last\n\
+                     ranking: 23456789TJQKA\n\
+                     precedence: one-pair two-pair high-card three-of-a-kind \
+                     full-house four-of-a-kind five-of-a-kind\n\
+                     \n\
+                     # queens are wild\n\
+                     name: wild queens\n\
+                     ranking: Q23456789TJKA\n\
+                     wild: Q\n";
+        let rule_sets: RuleSets = rules.parse().unwrap();
+
+        let pairs_last = rule_sets.get("pairs last").unwrap();
+        assert_eq!(rule_sets.get("jokers"), None);
+        let high_card = strength("A2345", pairs_last).unwrap();
+        let pair = strength("22345", pairs_last).unwrap();
+        assert!(high_card > pair);
+
+        assert_eq!(
+            winnings("A2345 1\n22345 2\n", &rule_sets),
+            Ok("pairs last: 4\nwild queens: 5\n".to_string())
+        );
+        assert_eq!(
+            winnings("QQ234 1\nKK234 2\n", &rule_sets),
+            Ok("pairs last: 5\nwild queens: 4\n".to_string())
+        );
+        assert_eq!(
+            winnings(TEST_INPUT, &RuleSets::default()),
+            Ok("standard: 6440\njokers: 5905\n".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_rule_sets() {
+        assert_eq!(
+            "name: x\nranking: 23424\n".parse::<RuleSets>(),
+            Err(ParseError::new(2, 13, "2", "a card not ranked yet"))
+        );
+        assert_eq!(
+            "name: x\nranking: 234\nwild: J\n".parse::<RuleSets>(),
+            Err(ParseError::new(3, 7, "J", "a ranked card"))
+        );
+        assert_eq!(
+            "name: x\nranking: 234\nprecedence: one-pair\n".parse::<RuleSets>(),
+            Err(ParseError::new(
+                3,
+                13,
+                "one-pair",
+                "every hand type, high-card included"
+            ))
+        );
+        assert_eq!(
+            "ranking: 234\n".parse::<RuleSets>(),
+            Err(ParseError::new(1, 1, "ranking: 234\n", "a name"))
+        );
+        assert_eq!(
+            "name: x\nranking: 234\n\nname: x\nranking: 432\n".parse::<RuleSets>(),
+            Err(ParseError::new(
+                4,
+                7,
+                "x",
+                "a name not given to another rule set"
+            ))
+        );
+
+        assert_eq!(
+            "name:\nranking: 234\n".parse::<RuleSets>(),
+            Err(ParseError::new(1, 6, "", "a non-empty name"))
+        );
+        assert_eq!(
+            "name: x\nranking: 234\nranking: 432\n".parse::<RuleSets>(),
+            Err(ParseError::new(3, 1, "ranking", "a key not given yet"))
+        );
+
+        let rule_sets: RuleSets = "name: small\nranking: 23456789\n".parse().unwrap();
+        assert_eq!(
+            winnings("22345 1\n2345A 2\n", &rule_sets),
+            Err(Error::no_solution("the small rules don't rank card A"))
+        );
     }
-    println!("part 2: {}", total_winnings(&mut data));
 }
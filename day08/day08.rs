@@ -1,32 +1,123 @@
+use common::{Error, ParseError};
 use std::collections::HashMap;
-use std::io;
-
-fn steps_until(
-    start: &String,
-    instructions: &String,
-    nodes: &HashMap<String, (String, String)>,
-    pred: impl Fn(&String) -> bool,
-) -> usize {
-    let mut current_node = start;
-
-    for (steps, instruction) in instructions.trim_end().chars().cycle().enumerate() {
-        if pred(current_node) {
-            return steps;
+
+// Nodes are referred to by their index in `names`.
+struct Network {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    // left and right neighbours of each node
+    next: Vec<[usize; 2]>,
+}
+
+impl Network {
+    fn node(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+}
+
+// Instructions as indices into `Network::next`, 0 for L and 1 for R.
+type Instructions = Vec<usize>;
+
+// A step of a walk: the node and the position of the instruction followed
+// from it.
+#[derive(Debug, PartialEq, Eq)]
+struct Step {
+    node: usize,
+    instruction: usize,
+}
+
+// The walk from `start` up to the first node satisfying `pred`, or none if the
+// walk loops forever without reaching one.
+fn walk(
+    start: usize,
+    instructions: &Instructions,
+    network: &Network,
+    pred: impl Fn(&str) -> bool,
+) -> Option<Vec<Step>> {
+    let n = instructions.len();
+    let mut visited = vec![false; network.names.len() * n];
+    let mut steps = Vec::new();
+    let mut node = start;
+
+    for step in 0.. {
+        let instruction = step % n;
+        steps.push(Step { node, instruction });
+        if pred(&network.names[node]) {
+            return Some(steps);
         }
-        let (l, r) = nodes
-            .get(current_node)
-            .expect(format!("invalid node: {}", current_node).as_str());
-        current_node = match instruction {
-            'L' => l,
-            'R' => r,
-            _ => panic!("invalid instruction: {}", instruction),
-        };
+
+        // back to a node at the same point of the instructions: the walk loops
+        let state = node * n + instruction;
+        if visited[state] {
+            return None;
+        }
+        visited[state] = true;
+
+        node = network.next[node][instructions[instruction]];
     }
 
     unreachable!()
 }
 
-fn gcd(a: usize, b: usize) -> usize {
+// How a walk settles into a loop: its state, the node and the position in the
+// instructions, repeats every `len` steps from step `start` on.
+#[derive(Debug, PartialEq, Eq)]
+struct Cycle {
+    start: usize,
+    len: usize,
+    // steps before the cycle at which the walk is on a target node
+    hits_before: Vec<usize>,
+    // steps into the cycle at which the walk is on a target node
+    offsets: Vec<usize>,
+}
+
+impl Cycle {
+    fn new(
+        start: usize,
+        instructions: &Instructions,
+        network: &Network,
+        pred: impl Fn(&str) -> bool,
+    ) -> Self {
+        let n = instructions.len();
+        let mut first_visit = vec![None; network.names.len() * n];
+        let mut hits = Vec::new();
+        let mut node = start;
+
+        for step in 0.. {
+            let state = node * n + step % n;
+            if let Some(cycle_start) = first_visit[state] {
+                let (hits_before, in_cycle) =
+                    hits.split_at(hits.partition_point(|&hit| hit < cycle_start));
+                return Cycle {
+                    start: cycle_start,
+                    len: step - cycle_start,
+                    hits_before: hits_before.to_vec(),
+                    offsets: in_cycle.iter().map(|hit| hit - cycle_start).collect(),
+                };
+            }
+            first_visit[state] = Some(step);
+
+            if pred(&network.names[node]) {
+                hits.push(step);
+            }
+            node = network.next[node][instructions[step % n]];
+        }
+
+        unreachable!()
+    }
+
+    fn hits(&self, step: usize) -> bool {
+        if step < self.start {
+            self.hits_before.binary_search(&step).is_ok()
+        } else {
+            self.offsets
+                .binary_search(&((step - self.start) % self.len))
+                .is_ok()
+        }
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
     let mut a = a;
     let mut b = b;
 
@@ -39,40 +130,339 @@ fn gcd(a: usize, b: usize) -> usize {
     a
 }
 
-fn main() {
-    let mut instructions = String::new();
-    io::stdin().read_line(&mut instructions).unwrap();
-
-    let mut line = String::new();
-    io::stdin().read_line(&mut line).unwrap();
-
-    let nodes: HashMap<String, (String, String)> = io::stdin()
-        .lines()
-        .map(|line| {
-            let line = line.expect("error reading input");
-            let (node, lr) = line.split_once(" = ").expect("error parsing input");
-            let (l, r) = lr
-                .strip_prefix('(')
-                .and_then(|s| s.strip_suffix(')'))
-                .and_then(|s| s.split_once(", "))
-                .expect("error parsing input");
-            (node.to_string(), (l.to_string(), r.to_string()))
+// Modular inverse of `a` modulo `m`, `a` and `m` being coprime.
+fn mod_inverse(a: u128, m: u128) -> u128 {
+    // extended Euclid on (a, m), tracking the coefficient of `a`
+    let (mut r0, mut r1) = (a as i128, m as i128);
+    let (mut s0, mut s1) = (1i128, 0i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - q * s1);
+    }
+
+    s0.rem_euclid(m as i128) as u128
+}
+
+// Solution of x = a (mod m) and x = b (mod n) as x = c (mod lcm(m, n)), if any,
+// the moduli not needing to be coprime.
+fn crt(a: u128, m: u128, b: u128, n: u128) -> Option<(u128, u128)> {
+    let g = gcd(m, n);
+    let diff = (b + n - a % n) % n;
+    if !diff.is_multiple_of(g) {
+        return None;
+    }
+
+    let lcm = m / g * n;
+    let k = diff / g * mod_inverse(m / g % (n / g), n / g) % (n / g);
+    Some(((a + m * k) % lcm, lcm))
+}
+
+// First step at which all the walks are on a target node together.
+fn sync(cycles: &[Cycle]) -> Option<usize> {
+    // steps before every walk is in its cycle are checked one by one
+    let settled = cycles.iter().map(|cycle| cycle.start).max().unwrap_or(0);
+    if let Some(step) = (0..settled).find(|&step| cycles.iter().all(|cycle| cycle.hits(step))) {
+        return Some(step);
+    }
+
+    // afterwards, a walk is on a target node at the steps congruent to one of
+    // its offsets modulo its length
+    let mut congruences = vec![(0, 1)];
+    for cycle in cycles {
+        let len = cycle.len as u128;
+        let mut combined: Vec<(u128, u128)> = congruences
+            .iter()
+            .flat_map(|&(a, m)| {
+                cycle.offsets.iter().filter_map(move |&offset| {
+                    let residue = (cycle.start + offset) as u128 % len;
+                    crt(a, m, residue, len)
+                })
+            })
+            .collect();
+        combined.sort();
+        combined.dedup();
+        congruences = combined;
+    }
+
+    congruences
+        .into_iter()
+        .map(|(a, m)| {
+            let settled = settled as u128;
+            if a >= settled {
+                a
+            } else {
+                a + (settled - a).div_ceil(m) * m
+            }
+        })
+        .min()
+        .map(|step| step as usize)
+}
+
+fn parse(input: &str) -> Result<(Instructions, Network), ParseError> {
+    let (instructions, nodes) = input
+        .split_once("\n\n")
+        .ok_or_else(|| ParseError::end_of(input, "a blank line after the instructions"))?;
+
+    let instructions = instructions.trim_end();
+    if instructions.is_empty() {
+        return Err(ParseError::at(input, instructions, "instructions"));
+    }
+    let instructions = instructions
+        .char_indices()
+        .map(|(i, c)| match c {
+            'L' => Ok(0),
+            'R' => Ok(1),
+            _ => Err(ParseError::at(
+                input,
+                &instructions[i..i + c.len_utf8()],
+                "L or R",
+            )),
+        })
+        .collect::<Result<Instructions, ParseError>>()?;
+
+    let mut network = Network {
+        names: Vec::new(),
+        index: HashMap::new(),
+        next: Vec::new(),
+    };
+    let mut references = Vec::new();
+    for line in nodes.lines() {
+        let (node, lr) = line
+            .split_once(" = ")
+            .ok_or_else(|| ParseError::at(input, line, "\"<node> = (<left>, <right>)\""))?;
+        let (l, r) = lr
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .and_then(|s| s.split_once(", "))
+            .ok_or_else(|| ParseError::at(input, lr, "\"(<left>, <right>)\""))?;
+
+        if network.index.contains_key(node) {
+            return Err(ParseError::at(input, node, "a node not defined before"));
+        }
+        network.index.insert(node.to_string(), network.names.len());
+        network.names.push(node.to_string());
+        references.push([l, r]);
+    }
+
+    // the whole network is known once all the nodes are read
+    for [l, r] in references {
+        let resolve = |name| {
+            network
+                .node(name)
+                .ok_or_else(|| ParseError::at(input, name, "a defined node"))
+        };
+        let next = [resolve(l)?, resolve(r)?];
+        network.next.push(next);
+    }
+
+    Ok((instructions, network))
+}
+
+/// The instructions and the network they're followed through.
+pub struct Parsed {
+    instructions: Instructions,
+    network: Network,
+}
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    let (instructions, network) = parse(input)?;
+
+    Ok(Parsed {
+        instructions,
+        network,
+    })
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    let Parsed {
+        instructions,
+        network,
+    } = parsed;
+
+    let start = network
+        .node("AAA")
+        .ok_or_else(|| Error::no_solution("there is no node AAA"))?;
+    let steps = walk(start, instructions, network, |node| node == "ZZZ")
+        .ok_or_else(|| Error::no_solution("no path leads from AAA to ZZZ"))?;
+
+    Ok(steps.len() - 1)
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let Parsed {
+        instructions,
+        network,
+    } = parsed;
+
+    let cycles: Vec<Cycle> = (0..network.names.len())
+        .filter(|&node| network.names[node].ends_with('A'))
+        .map(|node| Cycle::new(node, instructions, network, |node| node.ends_with('Z')))
+        .collect();
+    sync(&cycles).ok_or_else(|| {
+        Error::no_solution("the ghosts never stand on nodes ending with Z at the same step")
+    })
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+/// The walk from node `from` to the first node whose name ends with `to`, one
+/// line per step giving the node and the instruction followed from it.
+pub fn trace(input: &str, from: &str, to: &str) -> Result<String, Error> {
+    let (instructions, network) = parse(input)?;
+
+    let start = network
+        .node(from)
+        .ok_or_else(|| Error::NoSolution(format!("there is no node {}", from)))?;
+    let steps =
+        walk(start, &instructions, &network, |node| node.ends_with(to)).ok_or_else(|| {
+            Error::NoSolution(format!(
+                "no path leads from {} to a node ending with {}",
+                from, to
+            ))
+        })?;
+
+    let (last, steps) = steps.split_last().unwrap();
+    let mut trace: String = steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            let direction = ["L", "R"][instructions[step.instruction]];
+            format!(
+                "{}: {}, instruction {} ({})\n",
+                i, network.names[step.node], step.instruction, direction
+            )
         })
         .collect();
+    trace += &format!("{}: {}\n", steps.len(), network.names[last.node]);
 
-    let start = String::from("AAA");
-    println!(
-        "part 1: {}",
-        steps_until(&start, &instructions, &nodes, |node| node == "ZZZ")
-    );
-
-    println!(
-        "part 2: {}",
-        nodes
-            .keys()
-            .filter(|node| node.ends_with('A'))
-            .map(|node| steps_until(node, &instructions, &nodes, |node| node.ends_with('Z')))
-            .reduce(|acc, x| (acc * x) / gcd(acc, x))
-            .unwrap()
-    );
+    Ok(trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+    const TEST_INPUT1: &str = include_str!("test_input1");
+    const TEST_INPUT2: &str = include_str!("test_input2");
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(2));
+    }
+
+    #[test]
+    fn part1_test_input1() {
+        assert_eq!(part1(TEST_INPUT1), Ok(6));
+    }
+
+    #[test]
+    fn part2_test_input2() {
+        assert_eq!(part2(TEST_INPUT2), Ok(6));
+    }
+
+    #[test]
+    fn undefined_node() {
+        let input = "LR\n\nAAA = (BBB, ZZZ)\nZZZ = (ZZZ, ZZZ)\n";
+        assert_eq!(
+            part1(input),
+            Err(ParseError::new(3, 8, "BBB", "a defined node").into())
+        );
+    }
+
+    #[test]
+    fn cycles() {
+        let input = "L\n\n\
+                     1A = (1Z, 1Z)\n1Z = (1B, 1B)\n1B = (1Z, 1Z)\n\
+                     2A = (2B, 2B)\n2B = (2Z, 2Z)\n2Z = (2C, 2C)\n2C = (2B, 2B)\n";
+        let (instructions, network) = parse(input).unwrap();
+        let ends_with_z = |node: &str| node.ends_with('Z');
+        assert_eq!(
+            Cycle::new(
+                network.node("2A").unwrap(),
+                &instructions,
+                &network,
+                ends_with_z
+            ),
+            Cycle {
+                start: 1,
+                len: 3,
+                hits_before: vec![],
+                offsets: vec![1],
+            }
+        );
+
+        // the first hits, 1 and 2, aren't aligned with the cycles
+        assert_eq!(part2(input), Ok(5));
+    }
+
+    #[test]
+    fn hits_before_the_cycle() {
+        let input = "L\n\n\
+                     1A = (1Z, 1Z)\n1Z = (1B, 1B)\n1B = (1B, 1B)\n\
+                     2A = (2Z, 2Z)\n2Z = (2A, 2A)\n";
+        assert_eq!(part2(input), Ok(1));
+    }
+
+    #[test]
+    fn never_in_sync() {
+        let never = Err(Error::no_solution(
+            "the ghosts never stand on nodes ending with Z at the same step",
+        ));
+
+        // odd and even steps
+        let input = "L\n\n\
+                     1A = (1Z, 1Z)\n1Z = (1B, 1B)\n1B = (1Z, 1Z)\n\
+                     3A = (3B, 3B)\n3B = (3Z, 3Z)\n3Z = (3B, 3B)\n";
+        assert_eq!(part2(input), never);
+
+        // the first ghost leaves its target before the second one gets there
+        let input = "LR\n\n\
+                     1A = (1Z, 1Z)\n1Z = (1B, 1B)\n1B = (1B, 1B)\n\
+                     2A = (2B, 2B)\n2B = (2Z, 2Z)\n2Z = (2Z, 2Z)\n";
+        assert_eq!(part2(input), never);
+    }
+
+    #[test]
+    fn chinese_remainders() {
+        assert_eq!(crt(2, 3, 3, 5), Some((8, 15)));
+        assert_eq!(crt(1, 4, 3, 6), Some((9, 12)));
+        assert_eq!(crt(0, 4, 1, 6), None);
+    }
+
+    #[test]
+    fn walks() {
+        assert_eq!(
+            trace(TEST_INPUT, "AAA", "ZZZ"),
+            Ok("0: AAA, instruction 0 (R)\n1: CCC, instruction 1 (L)\n2: ZZZ\n".to_string())
+        );
+        assert_eq!(
+            trace(TEST_INPUT, "BBB", "Z"),
+            Err(Error::no_solution(
+                "no path leads from BBB to a node ending with Z"
+            ))
+        );
+        assert_eq!(
+            trace(TEST_INPUT, "XYZ", "Z"),
+            Err(Error::no_solution("there is no node XYZ"))
+        );
+        assert_eq!(
+            part1("L\n\nZZZ = (ZZZ, ZZZ)\n"),
+            Err(Error::no_solution("there is no node AAA"))
+        );
+
+        let input =
+            "LR\n\nAAA = (BBB, BBB)\nBBB = (CCC, AAA)\nCCC = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)\n";
+        assert_eq!(
+            part1(input),
+            Err(Error::no_solution("no path leads from AAA to ZZZ"))
+        );
+    }
 }
@@ -1,51 +1,276 @@
-use std::io;
+use common::{parse_token, Error, ParseError};
 
-fn generate_sequences(initial_sequence: &[i64]) -> Vec<Vec<i64>> {
-    let mut sequences = Vec::new();
-    sequences.push(initial_sequence.to_vec());
+// Why a sequence can't be extrapolated: the first two leave it without a
+// polynomial.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unfit {
+    NoZeroRow,
+    Overflow,
+    ValueOverflow,
+}
+
+impl Unfit {
+    fn description(self) -> &'static str {
+        match self {
+            Unfit::NoZeroRow => "no zero row",
+            Unfit::Overflow => "differences beyond 128 bits",
+            Unfit::ValueOverflow => "value beyond 128 bits",
+        }
+    }
+}
+
+// Polynomial given by its Newton forward differences: its value at x, counted
+// from the first value of the sequence, is the sum of `differences[k] * C(x, k)`.
+#[derive(Debug, PartialEq, Eq)]
+struct Polynomial {
+    // first values of the difference rows above the all-zero one
+    differences: Vec<i128>,
+}
+
+impl Polynomial {
+    // Fits `values`, which must reach a row of zero differences before running
+    // out of values.
+    fn fit(values: &[i64]) -> Result<Self, Unfit> {
+        let mut row: Vec<i128> = values.iter().map(|&v| v as i128).collect();
+        let mut differences = Vec::new();
+
+        while row.iter().any(|&x| x != 0) {
+            differences.push(row[0]);
+            row = row
+                .windows(2)
+                .map(|pair| pair[1].checked_sub(pair[0]))
+                .collect::<Option<Vec<i128>>>()
+                .ok_or(Unfit::Overflow)?;
+            if row.is_empty() {
+                return Err(Unfit::NoZeroRow);
+            }
+        }
+
+        Ok(Polynomial { differences })
+    }
+
+    // the zero polynomial counts as a constant
+    fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    // Value at `x`, none if it doesn't fit in 128 bits. `x` may be negative, the
+    // binomial coefficients being the generalized ones.
+    fn at(&self, x: i128) -> Option<i128> {
+        let mut value: i128 = 0;
+        let mut binomial: i128 = 1;
+
+        for (k, &difference) in self.differences.iter().enumerate() {
+            if k > 0 {
+                // C(x, k) = C(x, k - 1) * (x - k + 1) / k, the division being exact
+                let k = k as i128;
+                binomial = binomial.checked_mul(x.checked_sub(k - 1)?)? / k;
+            }
+            value = value.checked_add(difference.checked_mul(binomial)?)?;
+        }
 
-    while sequences.last().unwrap().iter().any(|&x| x != 0) {
-        let last_sequence = sequences.last().unwrap();
-        let next_sequence: Vec<i64> = last_sequence
-            .iter()
-            .zip(last_sequence.iter().skip(1))
-            .map(|(a, b)| b - a)
-            .collect();
-        sequences.push(next_sequence);
+        Some(value)
     }
+}
+
+struct Sequence {
+    // line number in the input
+    number: usize,
+    len: usize,
+    polynomial: Result<Polynomial, Unfit>,
+}
+
+impl Sequence {
+    // Value `steps` after the last one, or before the first one if negative.
+    fn value(&self, steps: i64) -> Result<i128, Unfit> {
+        let x = if steps >= 0 {
+            self.len as i128 - 1 + steps as i128
+        } else {
+            steps as i128
+        };
 
-    sequences
+        self.polynomial
+            .as_ref()
+            .map_err(|&unfit| unfit)?
+            .at(x)
+            .ok_or(Unfit::ValueOverflow)
+    }
+
+    fn extrapolate(&self, steps: i64) -> Result<i128, Error> {
+        self.value(steps).map_err(|unfit| {
+            Error::NoSolution(format!("line {}: {}", self.number, unfit.description()))
+        })
+    }
 }
 
-fn main() {
-    let history: Vec<Vec<i64>> = io::stdin()
+fn parse(input: &str) -> Result<Vec<Sequence>, ParseError> {
+    input
         .lines()
-        .map(|line| {
-            line.unwrap()
+        .enumerate()
+        .map(|(i, line)| {
+            let values = line
                 .split(' ')
-                .map(|s| s.parse::<i64>().expect("error parsing value"))
-                .collect()
+                .map(|s| parse_token(input, s, "a value"))
+                .collect::<Result<Vec<i64>, ParseError>>()?;
+            Ok(Sequence {
+                number: i + 1,
+                len: values.len(),
+                polynomial: Polynomial::fit(&values),
+            })
         })
-        .collect();
-
-    let sequences: Vec<Vec<Vec<i64>>> = history.iter().map(|seq| generate_sequences(seq)).collect();
-    println!(
-        "part 1: {}",
-        sequences
-            .iter()
-            .map(|seqs| seqs.iter().map(|seq| *seq.last().unwrap()).sum::<i64>())
-            .sum::<i64>()
-    );
-    println!(
-        "part 2: {}",
-        sequences
-            .iter()
-            .map(|seqs| seqs
-                .iter()
-                .rev()
-                .map(|seq| seq[0])
-                .reduce(|acc, x| x - acc)
-                .unwrap())
-            .sum::<i64>()
-    );
+        .collect()
+}
+
+// Sum of the values `steps` away from every sequence, failing on the first
+// sequence that can't be extrapolated.
+fn extrapolated_sum(sequences: &[Sequence], steps: i64) -> Result<i128, Error> {
+    sequences.iter().try_fold(0i128, |sum, sequence| {
+        sum.checked_add(sequence.extrapolate(steps)?)
+            .ok_or_else(|| Error::no_solution("the sum doesn't fit in 128 bits"))
+    })
+}
+
+/// The sequences with their polynomials fitted.
+pub struct Parsed(Vec<Sequence>);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<i128, Error> {
+    extrapolated_sum(&parsed.0, 1)
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<i128, Error> {
+    extrapolated_sum(&parsed.0, -1)
+}
+
+pub fn part1(input: &str) -> Result<i128, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<i128, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+/// The degree of each sequence and its value `steps` after its last value, or
+/// before its first one if negative, followed by the sum of these values. The
+/// sequences that can't be extrapolated are flagged and left out of the sum.
+pub fn extrapolate(input: &str, steps: i64) -> Result<String, Error> {
+    let mut report = String::new();
+    let mut sum: i128 = 0;
+    let mut skipped = 0;
+
+    let sequences = parse(input)?;
+    let count = sequences.len();
+    for sequence in &sequences {
+        let fitted = sequence
+            .polynomial
+            .as_ref()
+            .map_err(|&unfit| unfit)
+            .and_then(|polynomial| Ok((polynomial.degree(), sequence.value(steps)?)));
+        let (degree, value) = match fitted {
+            Ok(fitted) => fitted,
+            Err(unfit) => {
+                report += &format!("line {}: {}\n", sequence.number, unfit.description());
+                skipped += 1;
+                continue;
+            }
+        };
+        sum = sum
+            .checked_add(value)
+            .ok_or_else(|| Error::no_solution("the sum doesn't fit in 128 bits"))?;
+        report += &format!("line {}: degree {}, {}\n", sequence.number, degree, value);
+    }
+    report += &match skipped {
+        0 => format!("sum: {}\n", sum),
+        _ => format!(
+            "sum: {} over {} of {} sequences\n",
+            sum,
+            count - skipped,
+            count
+        ),
+    };
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn polynomials() {
+        let polynomial = Polynomial::fit(&[0, 3, 6, 9, 12, 15]).unwrap();
+        assert_eq!(polynomial.differences, vec![0, 3]);
+        assert_eq!(polynomial.degree(), 1);
+
+        // 10 13 16 21 30 45 are values of 10 + 3x + 2 * C(x, 3)
+        let polynomial = Polynomial::fit(&[10, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(Polynomial::fit(&[1, 2, 4]), Err(Unfit::NoZeroRow));
+        assert_eq!(polynomial.degree(), 3);
+        for x in -20..40i128 {
+            assert_eq!(
+                polynomial.at(x),
+                Some(10 + 3 * x + x * (x - 1) * (x - 2) / 3)
+            );
+        }
+        assert_eq!(polynomial.at(i128::MAX / 2), None);
+
+        // x^2 fits even though C(x, 3) doesn't
+        let polynomial = Polynomial::fit(&[0, 1, 4, 9]).unwrap();
+        let x = 4_000_000_000_000_000_000;
+        assert_eq!(polynomial.at(x), Some(x * x));
+        assert_eq!(polynomial.at(x * 4), None);
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(114));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(2));
+    }
+
+    #[test]
+    fn horizons() {
+        let sequences = parse(TEST_INPUT).unwrap();
+        assert_eq!(extrapolated_sum(&sequences, 0), Ok(15 + 21 + 45));
+        assert_eq!(
+            extrapolate("0 3 6 9 12 15\n1 3 6 10 15 21\n", 10),
+            Ok("line 1: degree 1, 45\nline 2: degree 2, 136\nsum: 181\n".to_string())
+        );
+        assert_eq!(
+            extrapolate("0 3 6 9 12 15\n", -3),
+            Ok("line 1: degree 1, -9\nsum: -9\n".to_string())
+        );
+        assert_eq!(
+            extrapolate("0 1 4 9\n0 1 8 27 64\n", 4_000_000_000_000_000_000),
+            Ok("line 1: degree 2, 16000000000000000024000000000000000009\n\
+                line 2: value beyond 128 bits\nsum: 16000000000000000024000000000000000009 \
+                over 1 of 2 sequences\n"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn no_zero_row() {
+        assert_eq!(
+            part1("0 1 2\n1 2 4 8\n"),
+            Err(Error::no_solution("line 2: no zero row"))
+        );
+        assert!(parse_input("0 1 2\n1 2 4 8\n").is_ok());
+        assert_eq!(
+            extrapolate("0 1 2\n1 2 4 8\n1 1\n", 1),
+            Ok(
+                "line 1: degree 1, 3\nline 2: no zero row\nline 3: degree 0, 1\n\
+                sum: 4 over 2 of 3 sequences\n"
+                    .to_string()
+            )
+        );
+    }
 }
@@ -1,142 +1,450 @@
-use std::collections::HashMap;
-use std::collections::VecDeque;
-use std::io;
-
-#[derive(Eq, Hash, PartialEq, Debug, Copy, Clone)]
-struct Point {
-    x: isize,
-    y: isize,
-}
-
-fn find_start(grid: &[Vec<char>]) -> Option<Point> {
-    for (y, row) in grid.iter().enumerate() {
-        for (x, &c) in row.iter().enumerate() {
-            if c == 'S' {
-                return Some(Point {
-                    x: x as isize,
-                    y: y as isize,
-                });
+use common::{Direction, Error, Grid, ParseError, Point};
+use std::collections::HashSet;
+
+// Directions in which a tile connects to its neighbours.
+fn connections(tile: char) -> &'static [Direction] {
+    match tile {
+        '|' => &[Direction::Up, Direction::Down],
+        '-' => &[Direction::Left, Direction::Right],
+        'L' => &[Direction::Up, Direction::Right],
+        'J' => &[Direction::Up, Direction::Left],
+        '7' => &[Direction::Down, Direction::Left],
+        'F' => &[Direction::Down, Direction::Right],
+        _ => &[],
+    }
+}
+
+fn find_start(grid: &Grid<char>) -> Option<Point> {
+    grid.position(|&c| c == 'S')
+}
+
+// Whether the tile at `p` connects back to a pipe reaching it going `direction`.
+fn connects_back(grid: &Grid<char>, p: Point, direction: Direction) -> bool {
+    grid.get(p)
+        .is_some_and(|&c| connections(c).contains(&direction.opposite()))
+}
+
+// The pipes from `start`, leaving it going `direction`, back to `start`, or up
+// to the pipe whose next connection goes astray.
+fn follow(grid: &Grid<char>, start: Point, direction: Direction) -> Result<Vec<Point>, Vec<Point>> {
+    let mut path = vec![start];
+    let mut p = start;
+    let mut direction = direction;
+
+    loop {
+        let next = p.step(direction);
+        if !connects_back(grid, next, direction) {
+            return Err(path);
+        }
+        if next == start {
+            return Ok(path);
+        }
+
+        path.push(next);
+        p = next;
+        // leave the pipe by its other end
+        direction = *connections(grid[p])
+            .iter()
+            .find(|&&d| d != direction.opposite())
+            .unwrap();
+    }
+}
+
+// A pipe the start tile could be, its ends meeting pipes that connect back.
+struct Candidate {
+    shape: char,
+    // the loop through the start tile, or the pipes up to where it goes astray
+    path: Result<Vec<Point>, Vec<Point>>,
+}
+
+fn start_candidates(start: Point, grid: &Grid<char>) -> Vec<Candidate> {
+    "|-LJ7F"
+        .chars()
+        .filter(|&shape| {
+            connections(shape)
+                .iter()
+                .all(|&d| connects_back(grid, start.step(d), d))
+        })
+        .map(|shape| {
+            let mut grid = grid.clone();
+            grid[start] = shape;
+            Candidate {
+                shape,
+                path: follow(&grid, start, connections(shape)[0]),
+            }
+        })
+        .collect()
+}
+
+// Loops of pipes not going through the start tile.
+fn closed_loops(grid: &Grid<char>) -> Vec<Vec<Point>> {
+    let mut seen = grid.map(|_| false);
+    let mut loops = Vec::new();
+
+    for (p, &c) in grid.iter() {
+        if seen[p] || c == 'S' || connections(c).is_empty() {
+            continue;
+        }
+
+        // a pipe not on a loop is on a chain open at both ends, walked both
+        // ways so that it's all seen at once
+        match follow(grid, p, connections(c)[0]) {
+            Ok(path) => {
+                for &q in &path {
+                    seen[q] = true;
+                }
+                loops.push(path);
+            }
+            Err(path) => {
+                let other_way = follow(grid, p, connections(c)[1]).unwrap_err();
+                for q in path.into_iter().chain(other_way) {
+                    seen[q] = true;
+                }
             }
         }
     }
-    None
+
+    loops
 }
 
-fn within_bounds(tile: &Point, grid: &[Vec<char>]) -> bool {
-    tile.y >= 0
-        && (tile.y as usize) < grid.len()
-        && tile.x >= 0
-        && (tile.x as usize) < grid[tile.y as usize].len()
+fn position(p: Point) -> String {
+    format!("line {}, column {}", p.y + 1, p.x + 1)
 }
 
-fn next_tile(tile: &Point, dx: isize, dy: isize, grid: &[Vec<char>]) -> Option<Point> {
-    let x = (tile.x as isize) + dx;
-    let y = (tile.y as isize) + dy;
-    if within_bounds(&Point { x, y }, grid) {
-        Some(Point { x, y })
-    } else {
-        None
+fn parse(input: &str) -> Result<Grid<char>, ParseError> {
+    let grid = Grid::parse(input, "a pipe, ground or the start", |c| {
+        "|-LJ7F.S".contains(c).then_some(c)
+    })?;
+
+    let mut starts = input.match_indices('S').map(|(i, _)| &input[i..i + 1]);
+    if starts.next().is_none() {
+        return Err(ParseError::end_of(input, "a start tile"));
+    }
+    if let Some(start) = starts.next() {
+        return Err(ParseError::at(input, start, "a single start tile"));
     }
+
+    Ok(grid)
 }
 
-fn connected_pipes(pipe: &Point, grid: &[Vec<char>]) -> (Option<Point>, Option<Point>) {
-    match grid[pipe.y as usize][pipe.x as usize] {
-        '|' => (next_tile(pipe, 0, -1, grid), next_tile(pipe, 0, 1, grid)),
-        '-' => (next_tile(pipe, -1, 0, grid), next_tile(pipe, 1, 0, grid)),
-        'L' => (next_tile(pipe, 0, -1, grid), next_tile(pipe, 1, 0, grid)),
-        'J' => (next_tile(pipe, 0, -1, grid), next_tile(pipe, -1, 0, grid)),
-        '7' => (next_tile(pipe, 0, 1, grid), next_tile(pipe, -1, 0, grid)),
-        'F' => (next_tile(pipe, 0, 1, grid), next_tile(pipe, 1, 0, grid)),
-        _ => (None, None),
+// The loop through the start tile, in order, the start tile being replaced by
+// its pipe. The start tile must be on exactly one loop.
+fn find_loop(input: &str, grid: &mut Grid<char>) -> Result<Vec<Point>, ParseError> {
+    let start = find_start(grid).unwrap();
+    let start_tile = &input[input.find('S').unwrap()..][..1];
+
+    let mut loops = start_candidates(start, grid)
+        .into_iter()
+        .filter_map(|candidate| Some((candidate.shape, candidate.path.ok()?)));
+    let (shape, path) = loops
+        .next()
+        .ok_or_else(|| ParseError::at(input, start_tile, "a start tile on a loop"))?;
+    if loops.next().is_some() {
+        return Err(ParseError::at(
+            input,
+            start_tile,
+            "a start tile on a single loop",
+        ));
     }
+
+    grid[start] = shape;
+    Ok(path)
 }
 
-fn next_tile_expect(
-    p: &Point,
-    dx: isize,
-    dy: isize,
-    grid: &[Vec<char>],
-    pat: &str,
-) -> Option<Point> {
-    if let Some(next) = next_tile(p, dx, dy, grid) {
-        if pat.find(grid[next.y as usize][next.x as usize]).is_some() {
-            Some(next)
-        } else {
-            None
+// Whether each tile is enclosed by the loop: crossing a pipe going up flips
+// between outside and inside.
+fn inside_tiles(grid: &Grid<char>, path: &[Point]) -> Grid<bool> {
+    let on_loop: HashSet<Point> = path.iter().copied().collect();
+    let mut inside = grid.map(|_| false);
+
+    for y in 0..grid.height() {
+        let mut enclosed = false;
+        for x in 0..grid.width() {
+            let p = Point::new(x as isize, y as isize);
+            if on_loop.contains(&p) {
+                if connections(grid[p]).contains(&Direction::Up) {
+                    enclosed = !enclosed;
+                }
+            } else {
+                inside[p] = enclosed;
+            }
         }
-    } else {
-        None
     }
+
+    inside
 }
 
-fn start_pipe(start: &Point, grid: &[Vec<char>]) -> char {
-    let north = next_tile_expect(&start, 0, -1, grid, "|7F");
-    let south = next_tile_expect(&start, 0, 1, grid, "|JL");
-    let east = next_tile_expect(&start, -1, 0, grid, "-LF");
-    let west = next_tile_expect(&start, 1, 0, grid, "-J7");
+// Tiles enclosed by the loop, from its area: by Pick's theorem, a lattice
+// polygon of area A with B boundary points encloses A - B / 2 + 1 points, and
+// the shoelace formula gives A.
+fn enclosed_tiles(path: &[Point]) -> usize {
+    let twice_area = path
+        .iter()
+        .zip(path.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<isize>()
+        .unsigned_abs();
 
-    match (north, south, east, west) {
-        (Some(_), Some(_), None, None) => '|',
-        (Some(_), None, Some(_), None) => 'J',
-        (Some(_), None, None, Some(_)) => 'L',
-        (None, Some(_), Some(_), None) => '7',
-        (None, Some(_), None, Some(_)) => 'F',
-        (None, None, Some(_), Some(_)) => '-',
-        _ => unreachable!(),
+    (twice_area + 2 - path.len()) / 2
+}
+
+// box-drawing version of a pipe
+fn box_drawing(tile: char) -> char {
+    match tile {
+        '|' => '│',
+        '-' => '─',
+        'L' => '└',
+        'J' => '┘',
+        '7' => '┐',
+        'F' => '┌',
+        _ => tile,
     }
 }
 
-fn main() {
-    let mut grid: Vec<Vec<char>> = io::stdin()
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect();
+/// The tiles of the loop through the start tile, in order.
+pub struct Parsed {
+    path: Vec<Point>,
+}
 
-    let start = find_start(&grid).unwrap();
-    grid[start.y as usize][start.x as usize] = start_pipe(&start, &grid);
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    let mut grid = parse(input)?;
 
-    let mut loop_pipes_distances: HashMap<Point, usize> = HashMap::new();
-    let mut q = VecDeque::new();
-    q.push_back((start, 0));
+    Ok(Parsed {
+        path: find_loop(input, &mut grid)?,
+    })
+}
 
-    while let Some((p, distance)) = q.pop_front() {
-        if loop_pipes_distances.contains_key(&p) {
-            continue;
-        }
-        loop_pipes_distances.insert(p, distance);
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    Ok(parsed.path.len() / 2)
+}
 
-        let (next1, next2) = connected_pipes(&p, &grid);
-        if let Some(next) = next1 {
-            q.push_back((next, distance + 1));
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    Ok(enclosed_tiles(&parsed.path))
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+/// The maze redrawn with box-drawing characters for the loop, `●` for the tiles
+/// inside it and `·` for the ones outside, followed by the enclosed tile counts
+/// from both methods.
+pub fn render(input: &str) -> Result<String, ParseError> {
+    let mut grid = parse(input)?;
+    let path = find_loop(input, &mut grid)?;
+    let on_loop: HashSet<Point> = path.iter().copied().collect();
+    let inside = inside_tiles(&grid, &path);
+
+    let mut rendered = String::new();
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            let p = Point::new(x as isize, y as isize);
+            rendered.push(match (on_loop.contains(&p), inside[p]) {
+                (true, _) => box_drawing(grid[p]),
+                (false, true) => '●',
+                (false, false) => '·',
+            });
         }
-        if let Some(next) = next2 {
-            q.push_back((next, distance + 1));
+        rendered.push('\n');
+    }
+
+    rendered += &format!(
+        "loop: {} tiles, farthest {} steps from the start\n\
+         enclosed: {} by Pick's theorem, {} by scanline\n",
+        path.len(),
+        path.len() / 2,
+        enclosed_tiles(&path),
+        inside.iter().filter(|(_, &inside)| inside).count()
+    );
+
+    Ok(rendered)
+}
+
+/// The pipes the start tile could be with the loop each would close or where
+/// it would go astray, followed by the loops not through the start tile.
+pub fn validate(input: &str) -> Result<String, ParseError> {
+    let grid = parse(input)?;
+    let start = find_start(&grid).unwrap();
+
+    let connected: Vec<String> = Direction::ALL
+        .into_iter()
+        .filter(|&d| connects_back(&grid, start.step(d), d))
+        .map(|d| format!("{:?}", d).to_lowercase())
+        .collect();
+    let mut report = format!(
+        "start: {}, pipes connecting from {}\n",
+        position(start),
+        if connected.is_empty() {
+            "nowhere".to_string()
+        } else {
+            connected.join(", ")
         }
+    );
+
+    let candidates = start_candidates(start, &grid);
+    if candidates.is_empty() {
+        report += "candidates: none\n";
+    }
+    for candidate in candidates {
+        report += &match candidate.path {
+            Ok(path) => format!(
+                "candidate {}: loop of {} tiles\n",
+                candidate.shape,
+                path.len()
+            ),
+            Err(path) => format!(
+                "candidate {}: goes astray after {}\n",
+                candidate.shape,
+                position(*path.last().unwrap())
+            ),
+        };
     }
 
-    println!("part 1: {}", loop_pipes_distances.values().max().unwrap());
-
-    let mut inside = false;
-    let mut count = 0;
-    for y in 0..grid.len() {
-        for x in 0..grid[y].len() {
-            let p = Point {
-                x: x as isize,
-                y: y as isize,
-            };
-            if loop_pipes_distances.contains_key(&p) {
-                let c = grid[y as usize][x as usize];
-                if c == '|' || c == 'J' || c == 'L' {
-                    inside = !inside;
-                }
-                continue;
-            }
+    let loops = closed_loops(&grid);
+    report += &format!("other loops: {}\n", loops.len());
+    for path in loops {
+        report += &format!("  {} tiles from {}\n", path.len(), position(path[0]));
+    }
 
-            if inside {
-                count += 1;
-            }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+    const TEST_INPUT1: &str = include_str!("test_input1");
+    const TEST_INPUT2: &str = include_str!("test_input2");
+    const TEST_INPUT3: &str = include_str!("test_input3");
+    const TEST_INPUT4: &str = include_str!("test_input4");
+    const TEST_INPUT5: &str = include_str!("test_input5");
+
+    #[test]
+    fn start_pipe_shape() {
+        let mut grid = parse(TEST_INPUT1).unwrap();
+        let start = find_start(&grid).unwrap();
+        find_loop(TEST_INPUT1, &mut grid).unwrap();
+        assert_eq!(grid[start], 'F');
+    }
+
+    #[test]
+    fn part1_test_inputs() {
+        assert_eq!(part1(TEST_INPUT), Ok(4));
+        assert_eq!(part1(TEST_INPUT1), Ok(8));
+    }
+
+    #[test]
+    fn part2_test_inputs() {
+        assert_eq!(part2(TEST_INPUT2), Ok(4));
+        assert_eq!(part2(TEST_INPUT3), Ok(4));
+        assert_eq!(part2(TEST_INPUT4), Ok(8));
+        assert_eq!(part2(TEST_INPUT5), Ok(10));
+    }
+
+    #[test]
+    fn pick_matches_scanline() {
+        for input in [TEST_INPUT2, TEST_INPUT3, TEST_INPUT4, TEST_INPUT5] {
+            let mut grid = parse(input).unwrap();
+            let path = find_loop(input, &mut grid).unwrap();
+            let inside = inside_tiles(&grid, &path);
+            assert_eq!(
+                enclosed_tiles(&path),
+                inside.iter().filter(|(_, &inside)| inside).count()
+            );
         }
     }
 
-    println!("part 2: {}", count);
+    #[test]
+    fn two_starts() {
+        assert_eq!(
+            part1("S-7\n|.|\nL-S\n"),
+            Err(ParseError::new(3, 3, "S", "a single start tile").into())
+        );
+    }
+
+    #[test]
+    fn rendering() {
+        assert_eq!(
+            render(TEST_INPUT2),
+            Ok("···········\n\
+                ·┌───────┐·\n\
+                ·│┌─────┐│·\n\
+                ·││·····││·\n\
+                ·││·····││·\n\
+                ·│└─┐·┌─┘│·\n\
+                ·│●●│·│●●│·\n\
+                ·└──┘·└──┘·\n\
+                ···········\n\
+                loop: 46 tiles, farthest 23 steps from the start\n\
+                enclosed: 4 by Pick's theorem, 4 by scanline\n"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn ambiguous_starts() {
+        // two loops through the start tile
+        let input = "F-7..\n|.|..\nL-S-7\n..|.|\n..L-J\n";
+        assert_eq!(
+            part1(input),
+            Err(ParseError::new(3, 3, "S", "a start tile on a single loop").into())
+        );
+        assert_eq!(
+            validate(input),
+            Ok(
+                "start: line 3, column 3, pipes connecting from up, down, left, right\n\
+                candidate |: goes astray after line 3, column 2\n\
+                candidate -: goes astray after line 2, column 3\n\
+                candidate L: goes astray after line 3, column 2\n\
+                candidate J: loop of 8 tiles\n\
+                candidate 7: goes astray after line 3, column 4\n\
+                candidate F: loop of 8 tiles\n\
+                other loops: 0\n"
+                    .to_string()
+            )
+        );
+
+        let input = ".....\n.S-7.\n.|.|.\n.L-..\n.....\n";
+        assert_eq!(
+            part1(input),
+            Err(ParseError::new(2, 2, "S", "a start tile on a loop").into())
+        );
+        assert_eq!(
+            validate(input),
+            Ok(
+                "start: line 2, column 2, pipes connecting from down, right\n\
+                candidate F: goes astray after line 4, column 3\n\
+                other loops: 0\n"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn other_loops() {
+        assert_eq!(
+            validate(TEST_INPUT),
+            Ok(
+                "start: line 2, column 2, pipes connecting from down, right\n\
+                candidate F: loop of 8 tiles\n\
+                other loops: 0\n"
+                    .to_string()
+            )
+        );
+        let input = "S7F7\nLJLJ\n";
+        assert_eq!(
+            validate(input),
+            Ok(
+                "start: line 1, column 1, pipes connecting from down, right\n\
+                candidate F: loop of 4 tiles\n\
+                other loops: 1\n  4 tiles from line 1, column 3\n"
+                    .to_string()
+            )
+        );
+    }
 }
@@ -1,69 +1,137 @@
-use std::io;
+use common::{Error, Grid, ParseError};
 
-struct Point {
-    x: usize,
-    y: usize,
+// The coordinates along one axis of galaxies at the given sorted lines of the
+// image, once each line without galaxies stands for `expansion` lines.
+fn expanded(lines: &[usize], expansion: u64) -> Vec<u128> {
+    let mut occupied = 0;
+    let mut previous = None;
+    lines
+        .iter()
+        .map(|&line| {
+            if previous != Some(line) {
+                occupied += 1;
+                previous = Some(line);
+            }
+            let empty = line + 1 - occupied;
+            (line - empty) as u128 + empty as u128 * expansion as u128
+        })
+        .collect()
 }
 
-fn find_galaxies(image: &[Vec<char>], expansion: usize) -> Vec<Point> {
-    let no_galaxy_rows: Vec<bool> = image
+// The sum of the distances between all pairs of sorted coordinates, each one
+// being that far from all the ones before it.
+fn distances_sum(coordinates: &[u128]) -> u128 {
+    let mut preceding = 0;
+    coordinates
         .iter()
-        .map(|row| row.iter().all(|&c| c == '.'))
-        .collect();
-    let no_galaxy_columns: Vec<bool> = (0..image[0].len())
-        .map(|x| (0..image.len()).map(|y| image[y][x]).all(|c| c == '.'))
-        .collect();
-    let mut galaxies: Vec<Point> = Vec::new();
-
-    let mut y_offset = 0;
-    for y in 0..image.len() {
-        let mut x_offset = 0;
-        if no_galaxy_rows[y] {
-            y_offset += expansion;
-            continue;
-        }
-
-        for x in 0..image[y].len() {
-            if no_galaxy_columns[x] {
-                x_offset += expansion;
-                continue;
-            }
+        .enumerate()
+        .map(|(i, &c)| {
+            let sum = c * i as u128 - preceding;
+            preceding += c;
+            sum
+        })
+        .sum()
+}
 
-            if image[y][x] == '#' {
-                galaxies.push(Point {
-                    x: x + x_offset,
-                    y: y + y_offset,
-                });
-            }
-        }
-    }
+fn parse(input: &str) -> Result<Grid<char>, ParseError> {
+    Grid::parse(input, "a galaxy or empty space", |c| {
+        (c == '#' || c == '.').then_some(c)
+    })
+}
 
-    galaxies
+/// The sorted columns and rows of the galaxies.
+pub struct Parsed {
+    columns: Vec<usize>,
+    rows: Vec<usize>,
 }
 
-fn manhattan_distance(a: &Point, b: &Point) -> usize {
-    a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    let image = parse(input)?;
+    let (mut columns, mut rows): (Vec<usize>, Vec<usize>) = image
+        .iter()
+        .filter(|&(_, &c)| c == '#')
+        .map(|(p, _)| (p.x as usize, p.y as usize))
+        .unzip();
+    columns.sort_unstable();
+    rows.sort_unstable();
+
+    Ok(Parsed { columns, rows })
 }
 
-fn distances_sum(galaxies: &[Point]) -> usize {
-    let mut sum = 0;
-    for i in 0..galaxies.len() {
-        for j in i..galaxies.len() {
-            sum += manhattan_distance(&galaxies[i], &galaxies[j]);
-        }
-    }
-    sum
+fn expanded_distances(parsed: &Parsed, row_expansion: u64, column_expansion: u64) -> u128 {
+    distances_sum(&expanded(&parsed.rows, row_expansion))
+        + distances_sum(&expanded(&parsed.columns, column_expansion))
+}
+
+/// The sum of the distances between all pairs of galaxies, once each row
+/// without galaxies stands for `row_expansion` rows and each such column for
+/// `column_expansion` columns.
+pub fn distances(
+    input: &str,
+    row_expansion: u64,
+    column_expansion: u64,
+) -> Result<u128, ParseError> {
+    Ok(expanded_distances(
+        &parse_input(input)?,
+        row_expansion,
+        column_expansion,
+    ))
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<u128, Error> {
+    Ok(expanded_distances(parsed, 2, 2))
 }
 
-fn main() {
-    let image: Vec<Vec<char>> = io::stdin()
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect();
+pub fn solve_part2(parsed: &Parsed) -> Result<u128, Error> {
+    Ok(expanded_distances(parsed, 1_000_000, 1_000_000))
+}
+
+pub fn part1(input: &str) -> Result<u128, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<u128, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
 
-    let galaxies = find_galaxies(&image, 1);
-    println!("part 1: {}", distances_sum(&galaxies));
+    #[test]
+    fn expansion() {
+        assert_eq!(distances(TEST_INPUT, 10, 10), Ok(1030));
+        assert_eq!(distances(TEST_INPUT, 100, 100), Ok(8410));
+    }
+
+    #[test]
+    fn axes() {
+        let rows: Vec<usize> = TEST_INPUT
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| line.matches('#').map(move |_| y))
+            .collect();
+        assert_eq!(expanded(&rows, 10), [0, 1, 2, 13, 14, 15, 26, 27, 27]);
+        assert_eq!(distances_sum(&[0, 1, 5]), 1 + 5 + 4);
+
+        // without expansion, and with empty lines dropped altogether
+        assert_eq!(distances(TEST_INPUT, 1, 1), Ok(292));
+        assert_eq!(distances(TEST_INPUT, 0, 0), Ok(210));
+        // both empty rows are crossed by 3 * 6 pairs of galaxies
+        assert_eq!(distances(TEST_INPUT, 10, 1), Ok(292 + 9 * (2 * 3 * 6)));
+    }
 
-    let galaxies = find_galaxies(&image, 1_000_000 - 1);
-    println!("part 2: {}", distances_sum(&galaxies));
+    #[test]
+    fn huge_expansion() {
+        let expansion = 10u64.pow(15);
+        let sum = distances(TEST_INPUT, expansion, expansion).unwrap();
+        assert_eq!(sum, 82 * (expansion as u128 - 1) + 292);
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(374));
+    }
 }
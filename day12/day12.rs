@@ -1,5 +1,5 @@
+use common::{parse_token, Error, ParseError};
 use std::collections::HashMap;
-use std::io;
 
 trait Predicate {
     fn ok(&self, c: char) -> bool;
@@ -90,7 +90,10 @@ fn match_count(
     count
 }
 
-fn solve(data: &[(Vec<char>, Vec<usize>)]) -> usize {
+// springs and damaged group sizes
+type Record = (Vec<char>, Vec<usize>);
+
+fn solve(data: &[Record]) -> usize {
     data.iter()
         .map(|(record, group_sizes)| {
             let mut mem = HashMap::new();
@@ -99,22 +102,43 @@ fn solve(data: &[(Vec<char>, Vec<usize>)]) -> usize {
         .sum::<usize>()
 }
 
-fn main() {
-    let data: Vec<(Vec<char>, Vec<usize>)> = io::stdin()
+fn parse(input: &str) -> Result<Vec<Record>, ParseError> {
+    input
         .lines()
         .map(|line| {
-            let line = line.expect("error reading input");
-            let (record, group_sizes) = line.split_once(' ').expect("error parsing input");
+            let (record, group_sizes) = line
+                .split_once(' ')
+                .ok_or_else(|| ParseError::at(input, line, "\"<record> <group sizes>\""))?;
+            if let Some((i, c)) = record.char_indices().find(|&(_, c)| !".#?".contains(c)) {
+                return Err(ParseError::at(
+                    input,
+                    &record[i..i + c.len_utf8()],
+                    "a spring condition",
+                ));
+            }
             let group_sizes = group_sizes
                 .split(',')
-                .map(|s| s.parse::<usize>().expect("error parsing group size"))
-                .collect();
-            (record.chars().collect(), group_sizes)
+                .map(|s| parse_token(input, s, "a group size"))
+                .collect::<Result<_, ParseError>>()?;
+            Ok((record.chars().collect(), group_sizes))
         })
-        .collect();
-    println!("part 1: {}", solve(&data));
+        .collect()
+}
+
+/// The condition records, folded as in the input.
+pub struct Parsed(Vec<Record>);
 
-    let unfolded_data: Vec<(Vec<char>, Vec<usize>)> = data
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    Ok(solve(&parsed.0))
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let unfolded_data: Vec<Record> = parsed
+        .0
         .iter()
         .map(|(record, group_sizes)| {
             let mut unfolded_record = Vec::new();
@@ -131,5 +155,48 @@ fn main() {
             (unfolded_record, unfolded_group_sizes)
         })
         .collect();
-    println!("part 2: {}", solve(&unfolded_data));
+    Ok(solve(&unfolded_data))
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn arrangements() {
+        let counts: Vec<usize> = parse(TEST_INPUT)
+            .unwrap()
+            .iter()
+            .map(|(record, group_sizes)| {
+                match_count(
+                    0,
+                    record,
+                    0,
+                    &create_pattern(group_sizes),
+                    &mut HashMap::new(),
+                )
+            })
+            .collect();
+        assert_eq!(counts, vec![1, 4, 1, 1, 4, 10]);
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(21));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(525152));
+    }
 }
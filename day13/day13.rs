@@ -1,4 +1,4 @@
-use std::io;
+use common::{Error, Grid, ParseError};
 use std::iter;
 
 fn reflection_line(lines: &[u32], smudges: usize) -> Option<usize> {
@@ -22,31 +22,29 @@ fn reflection_line(lines: &[u32], smudges: usize) -> Option<usize> {
             k += 1;
         }
 
-        if j < 0 || k >= lines.len() {
-            if n == smudges {
-                return Some(i + 1);
-            }
+        if (j < 0 || k >= lines.len()) && n == smudges {
+            return Some(i + 1);
         }
     }
 
     None
 }
 
-fn pattern_char_to_bit(c: u8) -> u32 {
+fn pattern_char_to_bit(c: char) -> Option<u32> {
     match c {
-        b'.' => 0,
-        b'#' => 1,
-        _ => panic!("invalid character: {}", c),
+        '.' => Some(0),
+        '#' => Some(1),
+        _ => None,
     }
 }
 
 fn solve(patterns_lines: &[Vec<u32>], patterns_cols: &[Vec<u32>], smudges: usize) -> usize {
     let (horizontal, vertical) = iter::zip(patterns_lines, patterns_cols)
         .map(|(lines, cols)| {
-            if let Some(reflection_line) = reflection_line(&lines, smudges) {
+            if let Some(reflection_line) = reflection_line(lines, smudges) {
                 (reflection_line, 0)
             } else {
-                (0, reflection_line(&cols, smudges).unwrap_or(0))
+                (0, reflection_line(cols, smudges).unwrap_or(0))
             }
         })
         .reduce(|(acc_h, acc_v), (h, v)| (acc_h + h, acc_v + v))
@@ -55,59 +53,88 @@ fn solve(patterns_lines: &[Vec<u32>], patterns_cols: &[Vec<u32>], smudges: usize
     vertical + 100 * horizontal
 }
 
-fn main() {
-    let input: Vec<Vec<u8>> = io::stdin()
-        .lines()
-        .map(|line| line.unwrap().bytes().collect())
-        .collect();
-    let pattern_boundaries: Vec<usize> = input
-        .iter()
-        .enumerate()
-        .filter(|(_, line)| line.len() == 0)
-        .map(|(i, _)| i)
-        .collect();
-    let patterns: Vec<&[Vec<u8>]> = iter::once(&input[0..pattern_boundaries[0]])
-        .chain(
-            pattern_boundaries
-                .iter()
-                .zip(
-                    pattern_boundaries
-                        .iter()
-                        .skip(1)
-                        .chain(iter::once(&input.len())),
-                )
-                .map(|(&start, &end)| &input[start + 1..end]),
-        )
-        .collect();
+// the lines and the columns of every pattern, as bit masks
+type Patterns = (Vec<Vec<u32>>, Vec<Vec<u32>>);
+
+fn parse(input: &str) -> Result<Patterns, ParseError> {
+    let patterns: Vec<Grid<u32>> = input
+        .split("\n\n")
+        .map(|pattern| {
+            Grid::parse(pattern, "ash or rock", pattern_char_to_bit)
+                .map_err(|e| e.rebase(input, pattern))
+        })
+        .collect::<Result<_, ParseError>>()?;
 
     let patterns_lines: Vec<Vec<u32>> = patterns
         .iter()
         .map(|pattern| {
             pattern
-                .iter()
-                .map(|line| {
-                    line.iter()
-                        .map(|&c| pattern_char_to_bit(c))
-                        .reduce(|acc, b| (acc << 1) | b)
-                        .unwrap()
-                })
+                .rows()
+                .map(|line| line.iter().fold(0, |acc, &b| (acc << 1) | b))
                 .collect()
         })
         .collect();
     let patterns_cols: Vec<Vec<u32>> = patterns
         .iter()
         .map(|pattern| {
-            (0..pattern[0].len())
-                .map(|x| {
-                    (0..pattern.len())
-                        .map(|y| pattern_char_to_bit(pattern[y][x]))
-                        .reduce(|acc, b| (acc << 1) | b)
-                        .unwrap()
-                })
+            pattern
+                .columns()
+                .map(|column| column.fold(0, |acc, &b| (acc << 1) | b))
                 .collect()
         })
         .collect();
 
-    println!("part 1: {}", solve(&patterns_lines, &patterns_cols, 0));
-    println!("part 2: {}", solve(&patterns_lines, &patterns_cols, 1));
+    Ok((patterns_lines, patterns_cols))
+}
+
+/// The rows and columns of each pattern as bit masks.
+pub struct Parsed(Patterns);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    let (patterns_lines, patterns_cols) = &parsed.0;
+    Ok(solve(patterns_lines, patterns_cols, 0))
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let (patterns_lines, patterns_cols) = &parsed.0;
+    Ok(solve(patterns_lines, patterns_cols, 1))
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn reflection_lines() {
+        let (patterns_lines, patterns_cols) = parse(TEST_INPUT).unwrap();
+        assert_eq!(reflection_line(&patterns_lines[0], 0), None);
+        assert_eq!(reflection_line(&patterns_cols[0], 0), Some(5));
+        assert_eq!(reflection_line(&patterns_lines[1], 0), Some(4));
+        assert_eq!(reflection_line(&patterns_lines[0], 1), Some(3));
+        assert_eq!(reflection_line(&patterns_lines[1], 1), Some(1));
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(405));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(400));
+    }
 }
@@ -1,5 +1,5 @@
+use common::{Error, Grid, ParseError, Point};
 use std::collections::HashMap;
-use std::io;
 
 #[derive(Copy, Clone, Eq, Hash, PartialEq)]
 enum Shape {
@@ -10,45 +10,34 @@ enum Shape {
 
 #[derive(Eq, Hash, PartialEq, Clone)]
 struct Platform {
-    map: Vec<Vec<Shape>>,
+    map: Grid<Shape>,
 }
 
 impl Platform {
     fn support_beams_load(&self) -> usize {
         self.map
-            .iter()
+            .rows()
             .enumerate()
             .map(|(y, row)| {
-                row.iter().filter(|&s| *s == Shape::RoundedRock).count() * (self.map.len() - y)
+                row.iter().filter(|&s| *s == Shape::RoundedRock).count() * (self.map.height() - y)
             })
             .sum()
     }
 
     fn rotate(&mut self) {
-        let n = self.map.len();
-
-        for y in 0..n / 2 {
-            for x in y..n - y - 1 {
-                let tmp = self.map[y][x];
-                self.map[y][x] = self.map[n - x - 1][y];
-                self.map[n - x - 1][y] = self.map[n - y - 1][n - x - 1];
-                self.map[n - y - 1][n - x - 1] = self.map[x][n - y - 1];
-                self.map[x][n - y - 1] = tmp;
-            }
-        }
+        self.map = self.map.rotate_clockwise();
     }
 
     fn tilt(&mut self) {
-        let n = self.map.len();
         // north
-        for x in 0..n {
+        for x in 0..self.map.width() as isize {
             let mut edge = 0;
-            for y in 0..n {
-                match self.map[y][x] {
+            for y in 0..self.map.height() as isize {
+                match self.map[Point::new(x, y)] {
                     Shape::Cube => edge = y + 1,
                     Shape::RoundedRock => {
-                        self.map[y][x] = Shape::Empty;
-                        self.map[edge][x] = Shape::RoundedRock;
+                        self.map[Point::new(x, y)] = Shape::Empty;
+                        self.map[Point::new(x, edge)] = Shape::RoundedRock;
                         edge += 1;
                     }
                     Shape::Empty => {}
@@ -65,29 +54,32 @@ impl Platform {
     }
 }
 
-fn main() {
-    let input: Vec<Vec<Shape>> = io::stdin()
-        .lines()
-        .map(|line| {
-            line.unwrap()
-                .chars()
-                .map(|c| match c {
-                    '#' => Shape::Cube,
-                    'O' => Shape::RoundedRock,
-                    '.' => Shape::Empty,
-                    _ => panic!("invalid character: {}", c),
-                })
-                .collect()
-        })
-        .collect();
-
-    let mut p = Platform { map: input };
-
-    {
-        let mut p = p.clone();
-        p.tilt();
-        println!("part 1: {}", p.support_beams_load());
-    }
+fn parse(input: &str) -> Result<Platform, ParseError> {
+    let map = Grid::parse(input, "a rock or empty space", |c| match c {
+        '#' => Some(Shape::Cube),
+        'O' => Some(Shape::RoundedRock),
+        '.' => Some(Shape::Empty),
+        _ => None,
+    })?;
+
+    Ok(Platform { map })
+}
+
+/// The platform before any tilt.
+pub struct Parsed(Platform);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    let mut p = parsed.0.clone();
+    p.tilt();
+    Ok(p.support_beams_load())
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let mut p = parsed.0.clone();
 
     let mut seen = HashMap::new();
     for i in 0.. {
@@ -99,10 +91,36 @@ fn main() {
             for _ in 0..cycles_left {
                 p.cycle();
             }
-            println!("part 2: {}", p.support_beams_load());
             break;
         } else {
             seen.insert(p.clone(), i);
         }
     }
+
+    Ok(p.support_beams_load())
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(136));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(64));
+    }
 }
@@ -1,5 +1,5 @@
+use common::{parse_token, Error, ParseError};
 use std::collections::VecDeque;
-use std::io;
 
 fn hash(s: &str) -> u8 {
     s.bytes().fold(0, |acc, c| {
@@ -8,56 +8,119 @@ fn hash(s: &str) -> u8 {
     })
 }
 
-fn main() {
-    let mut init_sequence = String::new();
-    io::stdin().read_line(&mut init_sequence).unwrap();
-    let steps: Vec<&str> = init_sequence.trim_end().split(',').collect();
+struct Step<'a> {
+    text: &'a str,
+    label: &'a str,
+    // `None` removes the lens
+    focal_length: Option<usize>,
+}
 
-    println!(
-        "part 1: {}",
-        steps.iter().map(|step| { hash(step) as u32 }).sum::<u32>()
-    );
+fn parse(input: &str) -> Result<Vec<Step<'_>>, ParseError> {
+    input
+        .trim_end()
+        .split(',')
+        .map(|text| {
+            let (label, focal_length) = if let Some((label, focal_length)) = text.split_once('=') {
+                let focal_length = parse_token(input, focal_length, "a focal length")?;
+                (label, Some(focal_length))
+            } else if let Some(label) = text.strip_suffix('-') {
+                (label, None)
+            } else {
+                return Err(ParseError::at(
+                    input,
+                    text,
+                    "\"<label>=<focal length>\" or \"<label>-\"",
+                ));
+            };
 
-    let mut boxes: [VecDeque<(&str, usize)>; 256] = std::array::from_fn(|_| VecDeque::new());
+            Ok(Step {
+                text,
+                label,
+                focal_length,
+            })
+        })
+        .collect()
+}
 
-    for step in steps {
-        if let Some((label, focal_length)) = step.split_once('=') {
-            let focal_length = focal_length.parse::<usize>().unwrap();
-            let box_id = hash(label) as usize;
-            let lens = boxes[box_id]
-                .iter_mut()
-                .enumerate()
-                .find(|(_, (l, _))| *l == label);
+/// The steps of the initialization sequence.
+pub struct Parsed<'a>(Vec<Step<'a>>);
 
-            if let Some((_, (_, fl))) = lens {
-                *fl = focal_length;
-            } else {
-                boxes[box_id].push_back((label, focal_length));
-            }
-        } else {
-            let label = &step[0..step.len() - 1];
-            let box_id = hash(label) as usize;
-            let lens = boxes[box_id]
-                .iter_mut()
-                .enumerate()
-                .find(|(_, (l, _))| *l == label);
+pub fn parse_input(input: &str) -> Result<Parsed<'_>, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<u32, Error> {
+    Ok(parsed
+        .0
+        .iter()
+        .map(|step| hash(step.text) as u32)
+        .sum::<u32>())
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let mut boxes: [VecDeque<(&str, usize)>; 256] = std::array::from_fn(|_| VecDeque::new());
+
+    for &Step {
+        label,
+        focal_length,
+        ..
+    } in &parsed.0
+    {
+        let box_id = hash(label) as usize;
+        let lens = boxes[box_id]
+            .iter_mut()
+            .enumerate()
+            .find(|(_, (l, _))| *l == label);
 
-            if let Some((i, _)) = lens {
+        match (lens, focal_length) {
+            (Some((_, (_, fl))), Some(focal_length)) => *fl = focal_length,
+            (None, Some(focal_length)) => boxes[box_id].push_back((label, focal_length)),
+            (Some((i, _)), None) => {
                 boxes[box_id].remove(i);
             }
+            (None, None) => {}
         }
     }
 
-    println!(
-        "part 2: {}",
-        boxes
-            .iter()
-            .enumerate()
-            .map(|(box_id, b)| b
-                .iter()
+    Ok(boxes
+        .iter()
+        .enumerate()
+        .flat_map(|(box_id, b)| {
+            b.iter()
                 .enumerate()
-                .map(move |(slot, (_, focal_length))| (box_id + 1) * (slot + 1) * focal_length))
-            .flatten()
-            .sum::<usize>()
-    );
+                .map(move |(slot, (_, focal_length))| (box_id + 1) * (slot + 1) * focal_length)
+        })
+        .sum::<usize>())
+}
+
+pub fn part1(input: &str) -> Result<u32, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn hash_algorithm() {
+        assert_eq!(hash("HASH"), 52);
+        assert_eq!(hash("rn"), 0);
+        assert_eq!(hash("qp"), 1);
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(1320));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(145));
+    }
 }
@@ -1,86 +1,32 @@
+use common::{Direction, Error, Grid, ParseError, Point};
 use std::collections::HashSet;
 use std::collections::VecDeque;
-use std::hash::Hash;
-use std::io;
 
-#[derive(Hash, Eq, PartialEq, Copy, Clone)]
-struct Point {
-    x: isize,
-    y: isize,
-}
-
-#[derive(Hash, Eq, PartialEq, Copy, Clone)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl Direction {
-    fn next_pos(&self, pos: &Point) -> Point {
-        match self {
-            Self::Up => Point {
-                x: pos.x,
-                y: pos.y - 1,
-            },
-            Self::Down => Point {
-                x: pos.x,
-                y: pos.y + 1,
-            },
-            Self::Left => Point {
-                x: pos.x - 1,
-                y: pos.y,
-            },
-            Self::Right => Point {
-                x: pos.x + 1,
-                y: pos.y,
-            },
-        }
-    }
-}
-
-fn within_bounds(p: &Point, cave: &[Vec<char>]) -> bool {
-    p.y >= 0 && (p.y as usize) < cave.len() && p.x >= 0 && (p.x as usize) < cave[p.y as usize].len()
-}
-
-fn energized(dir: Direction, pos: &Point, cave: &[Vec<char>]) -> usize {
+fn energized(dir: Direction, pos: Point, cave: &Grid<char>) -> usize {
     let mut visited = HashSet::new();
     let mut q = VecDeque::new();
-    q.push_back((dir, *pos));
+    q.push_back((dir, pos));
 
     while let Some((dir, pos)) = q.pop_front() {
-        if !within_bounds(&pos, &cave) {
+        let Some(&tile) = cave.get(pos) else {
             continue;
-        }
-        if visited.contains(&(dir, pos)) {
+        };
+        if !visited.insert((dir, pos)) {
             continue;
         }
-        visited.insert((dir, pos));
-
-        match cave[pos.y as usize][pos.x as usize] {
-            '.' => q.push_back((dir, dir.next_pos(&pos))),
-            '/' => match dir {
-                Direction::Up => q.push_back((Direction::Right, Direction::Right.next_pos(&pos))),
-                Direction::Down => q.push_back((Direction::Left, Direction::Left.next_pos(&pos))),
-                Direction::Left => q.push_back((Direction::Down, Direction::Down.next_pos(&pos))),
-                Direction::Right => q.push_back((Direction::Up, Direction::Up.next_pos(&pos))),
-            },
-            '\\' => match dir {
-                Direction::Up => q.push_back((Direction::Left, Direction::Left.next_pos(&pos))),
-                Direction::Down => q.push_back((Direction::Right, Direction::Right.next_pos(&pos))),
-                Direction::Left => q.push_back((Direction::Up, Direction::Up.next_pos(&pos))),
-                Direction::Right => q.push_back((Direction::Down, Direction::Down.next_pos(&pos))),
-            },
-            '|' => {
-                q.push_back((Direction::Up, Direction::Up.next_pos(&pos)));
-                q.push_back((Direction::Down, Direction::Down.next_pos(&pos)));
-            }
-            '-' => {
-                q.push_back((Direction::Left, Direction::Left.next_pos(&pos)));
-                q.push_back((Direction::Right, Direction::Right.next_pos(&pos)));
-            }
-            c => panic!("invalid character: {}", c),
+
+        let next_dirs = match (tile, dir) {
+            ('.', _) => vec![dir],
+            ('/', Direction::Up) | ('\\', Direction::Down) => vec![Direction::Right],
+            ('/', Direction::Down) | ('\\', Direction::Up) => vec![Direction::Left],
+            ('/', Direction::Left) | ('\\', Direction::Right) => vec![Direction::Down],
+            ('/', Direction::Right) | ('\\', Direction::Left) => vec![Direction::Up],
+            ('|', _) => vec![Direction::Up, Direction::Down],
+            ('-', _) => vec![Direction::Left, Direction::Right],
+            _ => unreachable!(),
+        };
+        for dir in next_dirs {
+            q.push_back((dir, pos.step(dir)));
         }
     }
 
@@ -91,51 +37,75 @@ fn energized(dir: Direction, pos: &Point, cave: &[Vec<char>]) -> usize {
         .len()
 }
 
-fn main() {
-    let cave: Vec<Vec<char>> = io::stdin()
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect();
-
-    let (dir, pos) = (Direction::Right, Point { x: 0, y: 0 });
-    println!("part 1: {}", energized(dir, &pos, &cave));
-
-    let max_energized = (0..cave.len())
-        .map(|i| {
-            energized(
-                Direction::Down,
-                &Point {
-                    x: i as isize,
-                    y: 0,
-                },
-                &cave,
-            )
-            .max(energized(
-                Direction::Up,
-                &Point {
-                    x: i as isize,
-                    y: (cave.len() - 1) as isize,
-                },
-                &cave,
-            ))
-            .max(energized(
-                Direction::Right,
-                &Point {
-                    x: 0,
-                    y: i as isize,
-                },
-                &cave,
-            ))
-            .max(energized(
-                Direction::Left,
-                &Point {
-                    x: (cave[0].len() - 1) as isize,
-                    y: i as isize,
-                },
-                &cave,
-            ))
-        })
+fn parse(input: &str) -> Result<Grid<char>, ParseError> {
+    Grid::parse(input, "a mirror, splitter or empty space", |c| {
+        "./\\|-".contains(c).then_some(c)
+    })
+}
+
+/// The contraption's mirrors and splitters.
+pub struct Parsed(Grid<char>);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    Ok(energized(Direction::Right, Point::new(0, 0), &parsed.0))
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let cave = &parsed.0;
+    let (width, height) = (cave.width() as isize, cave.height() as isize);
+
+    let vertical = (0..width).flat_map(|x| {
+        [
+            (Direction::Down, Point::new(x, 0)),
+            (Direction::Up, Point::new(x, height - 1)),
+        ]
+    });
+    let horizontal = (0..height).flat_map(|y| {
+        [
+            (Direction::Right, Point::new(0, y)),
+            (Direction::Left, Point::new(width - 1, y)),
+        ]
+    });
+
+    Ok(vertical
+        .chain(horizontal)
+        .map(|(dir, pos)| energized(dir, pos, cave))
         .max()
-        .unwrap();
-    println!("part 2: {}", max_energized);
+        .unwrap())
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn energized_tiles() {
+        let cave = parse(TEST_INPUT).unwrap();
+        assert_eq!(energized(Direction::Right, Point::new(0, 0), &cave), 46);
+        assert_eq!(energized(Direction::Down, Point::new(3, 0), &cave), 51);
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(46));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(51));
+    }
 }
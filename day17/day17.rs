@@ -1,66 +1,7 @@
+use common::{Direction, Error, Grid, ParseError, Point};
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 use std::collections::HashMap;
-use std::io;
-
-#[derive(Hash, Eq, PartialEq, Copy, Clone, Ord, PartialOrd)]
-struct Point {
-    x: isize,
-    y: isize,
-}
-
-#[derive(Hash, Eq, PartialEq, Copy, Clone, Ord, PartialOrd)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl Direction {
-    fn next_pos(&self, pos: &Point) -> Point {
-        match self {
-            Self::Up => Point {
-                x: pos.x,
-                y: pos.y - 1,
-            },
-            Self::Down => Point {
-                x: pos.x,
-                y: pos.y + 1,
-            },
-            Self::Left => Point {
-                x: pos.x - 1,
-                y: pos.y,
-            },
-            Self::Right => Point {
-                x: pos.x + 1,
-                y: pos.y,
-            },
-        }
-    }
-
-    fn turn_left(&self) -> Self {
-        match self {
-            Self::Up => Self::Left,
-            Self::Down => Self::Right,
-            Self::Left => Self::Down,
-            Self::Right => Self::Up,
-        }
-    }
-
-    fn turn_right(&self) -> Self {
-        match self {
-            Self::Up => Self::Right,
-            Self::Down => Self::Left,
-            Self::Left => Self::Up,
-            Self::Right => Self::Down,
-        }
-    }
-}
-
-fn within_bounds(p: &Point, map: &[Vec<u32>]) -> bool {
-    p.y >= 0 && (p.y as usize) < map.len() && p.x >= 0 && (p.x as usize) < map[p.y as usize].len()
-}
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
 struct Crucible {
@@ -114,7 +55,7 @@ impl PartialOrd for State {
     }
 }
 
-fn solve(crucible: &Crucible, goal: &Point, map: &[Vec<u32>]) -> Option<u32> {
+fn solve(crucible: &Crucible, goal: &Point, map: &Grid<u32>) -> Option<u32> {
     let mut losses: HashMap<Crucible, u32> = HashMap::new();
 
     let mut pq = BinaryHeap::new();
@@ -154,19 +95,19 @@ fn solve(crucible: &Crucible, goal: &Point, map: &[Vec<u32>]) -> Option<u32> {
             if next_dir != dir && dir_count < min_dir_count {
                 continue;
             }
-            let next_pos = next_dir.next_pos(&pos);
-            if !within_bounds(&next_pos, map) {
+            let next_pos = pos.step(next_dir);
+            let Some(&next_loss) = map.get(next_pos) else {
                 continue;
-            }
+            };
 
             let next = State {
-                heat_loss: heat_loss + map[next_pos.y as usize][next_pos.x as usize],
+                heat_loss: heat_loss + next_loss,
                 crucible: Crucible {
                     pos: next_pos,
                     dir: next_dir,
                     dir_count: next_dir_count,
-                    max_dir_count: max_dir_count,
-                    min_dir_count: min_dir_count,
+                    max_dir_count,
+                    min_dir_count,
                 },
             };
 
@@ -188,31 +129,71 @@ fn solve(crucible: &Crucible, goal: &Point, map: &[Vec<u32>]) -> Option<u32> {
         .min()
 }
 
-fn main() {
-    let map: Vec<Vec<u32>> = io::stdin()
-        .lines()
-        .map(|line| {
-            line.unwrap()
-                .chars()
-                .map(|c| c.to_digit(10).expect("map character is not a digit"))
-                .collect()
-        })
-        .collect();
-
-    let goal = Point {
-        x: (map[0].len() - 1) as isize,
-        y: (map.len() - 1) as isize,
-    };
-
-    let crucible = Crucible::new_standard(Point { x: 0, y: 0 }, Direction::Right);
-    println!(
-        "part 1: {}",
-        solve(&crucible, &goal, &map).expect("no solution for part 1")
-    );
-
-    let crucible = Crucible::new_ultra(Point { x: 0, y: 0 }, Direction::Right);
-    println!(
-        "part 2: {}",
-        solve(&crucible, &goal, &map).expect("no solution for part 2")
-    );
+fn parse(input: &str) -> Result<Grid<u32>, ParseError> {
+    Grid::parse(input, "a heat loss digit", |c| c.to_digit(10))
+}
+
+fn goal(map: &Grid<u32>) -> Point {
+    Point::new(map.width() as isize - 1, map.height() as isize - 1)
+}
+
+/// The heat loss of each city block.
+pub struct Parsed(Grid<u32>);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<u32, Error> {
+    let map = &parsed.0;
+
+    let crucible = Crucible::new_standard(Point::new(0, 0), Direction::Right);
+    solve(&crucible, &goal(map), map)
+        .ok_or_else(|| Error::no_solution("the crucible can't stop at the bottom-right block"))
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<u32, Error> {
+    let map = &parsed.0;
+
+    let crucible = Crucible::new_ultra(Point::new(0, 0), Direction::Right);
+    solve(&crucible, &goal(map), map).ok_or_else(|| {
+        Error::no_solution("the ultra crucible can't stop at the bottom-right block")
+    })
+}
+
+pub fn part1(input: &str) -> Result<u32, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<u32, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+    const TEST_INPUT1: &str = include_str!("test_input1");
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(102));
+    }
+
+    #[test]
+    fn part2_test_inputs() {
+        assert_eq!(part2(TEST_INPUT), Ok(94));
+        assert_eq!(part2(TEST_INPUT1), Ok(71));
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        assert_eq!(
+            part2("1\n"),
+            Err(Error::no_solution(
+                "the ultra crucible can't stop at the bottom-right block"
+            ))
+        );
+    }
 }
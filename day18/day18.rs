@@ -1,5 +1,5 @@
+use common::{parse_token, Error, ParseError};
 use std::collections::HashMap;
-use std::io;
 
 #[derive(Hash, Eq, PartialEq, Copy, Clone, Ord, PartialOrd, Debug)]
 struct Point {
@@ -39,7 +39,7 @@ fn intersection(r1: &Range, r2: &Range) -> Option<Range> {
 }
 
 fn todo(done: &[Range], range: &Range) -> Option<Range> {
-    if done.len() == 0 || range.end < done[0].start || range.start > done.last().unwrap().end {
+    if done.is_empty() || range.end < done[0].start || range.start > done.last().unwrap().end {
         return Some(*range);
     }
 
@@ -65,15 +65,15 @@ fn todo(done: &[Range], range: &Range) -> Option<Range> {
     }
 
     if i == 0 {
-        return Some(Range {
+        Some(Range {
             start: range.start,
             end: done[i].start,
-        });
+        })
     } else if i == done.len() {
-        return Some(Range {
+        Some(Range {
             start: done[i - 1].end,
             end: range.end,
-        });
+        })
     } else {
         let start = if range.start <= done[i - 1].end {
             done[i - 1].end
@@ -85,7 +85,7 @@ fn todo(done: &[Range], range: &Range) -> Option<Range> {
         } else {
             range.end
         };
-        return Some(Range { start, end });
+        Some(Range { start, end })
     }
 }
 
@@ -117,7 +117,8 @@ fn add_done_range(done: &mut Vec<Range>, range: &Range) -> Option<(isize, isize)
     merged
 }
 
-fn lagoon_capacity(dig_plan: &[DigInstruction]) -> usize {
+// None for a plan that doesn't enclose a lagoon.
+fn lagoon_capacity(dig_plan: &[DigInstruction]) -> Option<usize> {
     let mut v_ranges: Vec<(isize, Range)> = Vec::new();
 
     let start = Point { x: 0, y: 0 };
@@ -152,6 +153,9 @@ fn lagoon_capacity(dig_plan: &[DigInstruction]) -> usize {
         };
     }
 
+    if pos != start {
+        return None;
+    }
     v_ranges.sort();
 
     let mut capacity = 0;
@@ -179,14 +183,13 @@ fn lagoon_capacity(dig_plan: &[DigInstruction]) -> usize {
                         start: v_ranges[i].0,
                         end: v_ranges[j].0,
                     };
-                    if let Some((inter_end, _)) = add_done_range(
-                        top_bottom.entry(inter.start).or_insert(Vec::new()),
-                        &h_range,
-                    ) {
+                    if let Some((inter_end, _)) =
+                        add_done_range(top_bottom.entry(inter.start).or_default(), &h_range)
+                    {
                         capacity -= inter_end - h_range.start + 1;
                     }
                     if let Some((inter_end, _)) =
-                        add_done_range(top_bottom.entry(inter.end).or_insert(Vec::new()), &h_range)
+                        add_done_range(top_bottom.entry(inter.end).or_default(), &h_range)
                     {
                         capacity -= inter_end - h_range.start + 1;
                     }
@@ -194,45 +197,76 @@ fn lagoon_capacity(dig_plan: &[DigInstruction]) -> usize {
             }
             j += 1;
         }
+        // a trench left without another one facing it
         if !range_done(&v_ranges[i].1, &done[i]) {
-            panic!("{}: {:?} , done[{}] = {:?}", i, v_ranges[i], i, done[i]);
+            return None;
         }
     }
 
-    capacity as usize
+    Some(capacity as usize)
 }
 
-fn main() {
-    let dig_plan: Vec<DigInstruction> = io::stdin()
+fn parse(input: &str) -> Result<Vec<DigInstruction>, ParseError> {
+    input
         .lines()
         .map(|line| {
-            let line = line.unwrap();
-            let (direction, rest) = line.split_once(' ').expect("invalid input");
-            let (distance, color) = rest.split_once(' ').expect("invalid input");
+            let mut fields = line.split(' ');
+            let (Some(direction), Some(distance), Some(color), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(ParseError::at(
+                    input,
+                    line,
+                    "\"<direction> <distance> (#<colour>)\"",
+                ));
+            };
             let direction = match direction {
                 "U" => Direction::Up,
                 "D" => Direction::Down,
                 "L" => Direction::Left,
                 "R" => Direction::Right,
-                _ => panic!("invalid direction: {}", direction),
+                _ => return Err(ParseError::at(input, direction, "U, D, L or R")),
             };
-            let distance: usize = distance.parse().expect("error parsing distance");
-            let color = color
+            let distance: usize = parse_token(input, distance, "a distance")?;
+            let hex = color
                 .strip_prefix("(#")
                 .and_then(|s| s.strip_suffix(')'))
-                .expect("error parseing color");
-            let color = u32::from_str_radix(color, 16).expect("error parsing color");
+                .filter(|hex| hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .ok_or_else(|| ParseError::at(input, color, "\"(#<6 hex digits>)\""))?;
+            // the last digit encodes the direction in part 2
+            if !"0123".contains(&hex[5..]) {
+                return Err(ParseError::at(input, &hex[5..], "a direction digit (0-3)"));
+            }
+            let color = u32::from_str_radix(hex, 16).unwrap();
 
-            DigInstruction {
+            Ok(DigInstruction {
                 direction,
                 distance,
                 color,
-            }
+            })
         })
-        .collect();
-    println!("part 1: {}", lagoon_capacity(&dig_plan));
+        .collect()
+}
+
+/// The dig plan with the colors part 2 reads its instructions from.
+pub struct Parsed(Vec<DigInstruction>);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+fn enclosed_capacity(dig_plan: &[DigInstruction]) -> Result<usize, Error> {
+    lagoon_capacity(dig_plan)
+        .ok_or_else(|| Error::no_solution("the dig plan doesn't enclose a lagoon"))
+}
 
-    let dig_plan: Vec<DigInstruction> = dig_plan
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    enclosed_capacity(&parsed.0)
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let dig_plan: Vec<DigInstruction> = parsed
+        .0
         .iter()
         .map(|instruction| {
             let distance = (instruction.color >> 4) & 0xfffff;
@@ -241,7 +275,7 @@ fn main() {
                 1 => Direction::Down,
                 2 => Direction::Left,
                 3 => Direction::Up,
-                _ => panic!("invalid direction in hex code"),
+                _ => unreachable!(),
             };
             DigInstruction {
                 direction,
@@ -250,5 +284,63 @@ fn main() {
             }
         })
         .collect();
-    println!("part 2: {}", lagoon_capacity(&dig_plan));
+    enclosed_capacity(&dig_plan)
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn capacity() {
+        let square = [
+            (Direction::Right, 2),
+            (Direction::Down, 2),
+            (Direction::Left, 2),
+            (Direction::Up, 2),
+        ]
+        .map(|(direction, distance)| DigInstruction {
+            direction,
+            distance,
+            color: 0,
+        });
+        assert_eq!(lagoon_capacity(&square), Some(9));
+        assert_eq!(lagoon_capacity(&square[..3]), None);
+    }
+
+    #[test]
+    fn open_plan() {
+        assert_eq!(
+            part1("R 2 (#000000)\nD 2 (#000000)\n"),
+            Err(Error::no_solution("the dig plan doesn't enclose a lagoon"))
+        );
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(62));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(952408144115));
+    }
+
+    #[test]
+    fn invalid_direction() {
+        assert_eq!(
+            part1("R 6 (#70c710)\nX 5 (#0dc571)\n"),
+            Err(ParseError::new(2, 1, "X", "U, D, L or R").into())
+        );
+    }
 }
@@ -1,6 +1,6 @@
+use common::{parse_token, Error, ParseError};
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::io;
 use std::str::FromStr;
 
 #[derive(Debug)]
@@ -26,21 +26,28 @@ impl Condition {
     }
 }
 
-struct ParseConditionError;
+const RATINGS: [&str; 4] = ["x", "m", "a", "s"];
 
 impl FromStr for Condition {
-    type Err = ParseConditionError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let rating = s[0..1].to_string();
-        let cmp = match &s[1..2] {
-            "<" => Comparison::LessThan,
-            ">" => Comparison::GreaterThan,
-            _ => return Err(ParseConditionError),
+        let rating = s
+            .get(0..1)
+            .filter(|rating| RATINGS.contains(rating))
+            .ok_or_else(|| ParseError::at(s, s, "x, m, a or s"))?;
+        let cmp = match s.get(1..2) {
+            Some("<") => Comparison::LessThan,
+            Some(">") => Comparison::GreaterThan,
+            _ => return Err(ParseError::at(s, &s[1..], "< or >")),
         };
-        let value: u32 = s[2..].parse().map_err(|_| ParseConditionError)?;
+        let value: u32 = parse_token(s, &s[2..], "a rating value")?;
 
-        Ok(Condition { rating, cmp, value })
+        Ok(Condition {
+            rating: rating.to_string(),
+            cmp,
+            value,
+        })
     }
 }
 
@@ -60,15 +67,13 @@ impl Rule {
     }
 }
 
-struct ParseRuleError;
-
 impl FromStr for Rule {
-    type Err = ParseRuleError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if let Some((condition, destination)) = s.split_once(':') {
             Ok(Rule {
-                condition: Some(condition.parse().map_err(|_| ParseRuleError)?),
+                condition: Some(condition.parse()?),
                 destination: destination.to_string(),
             })
         } else {
@@ -96,11 +101,8 @@ impl Workflow {
     }
 }
 
-#[derive(Debug)]
-struct ParseWorkflowError;
-
 impl FromStr for Workflow {
-    type Err = ParseWorkflowError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut rules: Vec<Rule> = Vec::new();
@@ -108,10 +110,20 @@ impl FromStr for Workflow {
         for rule in s
             .strip_prefix('{')
             .and_then(|s| s.strip_suffix('}'))
-            .ok_or(ParseWorkflowError)?
+            .ok_or_else(|| ParseError::at(s, s, "\"{<rules>}\""))?
             .split(',')
         {
-            rules.push(rule.parse::<Rule>().map_err(|_| ParseWorkflowError)?);
+            rules.push(
+                rule.parse::<Rule>()
+                    .map_err(|e: ParseError| e.rebase(s, rule))?,
+            );
+        }
+        if rules.last().is_none_or(|rule| rule.condition.is_some()) {
+            return Err(ParseError::at(
+                s,
+                &s[s.len() - 1..],
+                "a final unconditional rule",
+            ));
         }
 
         Ok(Workflow { rules })
@@ -123,11 +135,8 @@ struct Part {
     ratings: HashMap<String, u32>,
 }
 
-#[derive(Debug)]
-struct ParsePartError;
-
 impl FromStr for Part {
-    type Err = ParsePartError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut ratings = HashMap::new();
@@ -135,13 +144,21 @@ impl FromStr for Part {
         for rating in s
             .strip_prefix('{')
             .and_then(|s| s.strip_suffix('}'))
-            .ok_or(ParsePartError)?
+            .ok_or_else(|| ParseError::at(s, s, "\"{<ratings>}\""))?
             .split(',')
         {
-            let (rating, value) = rating.split_once('=').ok_or(ParsePartError)?;
-            let value = value.parse::<u32>().map_err(|_| ParsePartError)?;
+            let (rating, value) = rating
+                .split_once('=')
+                .ok_or_else(|| ParseError::at(s, rating, "\"<rating>=<value>\""))?;
+            if !RATINGS.contains(&rating) {
+                return Err(ParseError::at(s, rating, "x, m, a or s"));
+            }
+            let value: u32 = parse_token(s, value, "a rating value")?;
             ratings.insert(rating.to_string(), value);
         }
+        if ratings.len() != RATINGS.len() {
+            return Err(ParseError::at(s, s, "ratings for x, m, a and s"));
+        }
 
         Ok(Part { ratings })
     }
@@ -159,7 +176,7 @@ fn accepted(part: &Part, workflows: &HashMap<String, Workflow>) -> bool {
 
 fn solve(
     invalid_rating_values: &mut HashMap<String, Vec<(u32, u32)>>,
-    workflow: &String,
+    workflow: &str,
     workflows: &HashMap<String, Workflow>,
 ) -> usize {
     if workflow.eq("A") {
@@ -221,54 +238,125 @@ fn solve(
     result
 }
 
-fn main() {
-    let mut workflows = HashMap::new();
-
-    loop {
-        let mut input = String::new();
-        match io::stdin().read_line(&mut input) {
-            Ok(0) => panic!("unexpected end of input"),
-            Ok(1) => break,
-            Ok(_) => {
-                let brace = input.find('{').expect("error parsing input");
-                let name = input[..brace].to_string();
-                let workflow: Workflow = input[brace..]
-                    .trim_end()
-                    .parse()
-                    .expect("error parsing workflow");
-                workflows.insert(name, workflow);
-            }
-            Err(e) => panic!("error reading input: {}", e),
-        }
+fn parse(input: &str) -> Result<(HashMap<String, Workflow>, Vec<Part>), ParseError> {
+    let (workflows_str, parts_str) = input
+        .split_once("\n\n")
+        .ok_or_else(|| ParseError::end_of(input, "a blank line after the workflows"))?;
+
+    let mut destinations = Vec::new();
+    let workflows: HashMap<String, Workflow> = workflows_str
+        .lines()
+        .map(|line| {
+            let brace = line
+                .find('{')
+                .ok_or_else(|| ParseError::at(input, line, "\"<name>{<rules>}\""))?;
+            let name = line[..brace].to_string();
+            let rules = &line[brace..];
+            let workflow: Workflow = rules
+                .parse()
+                .map_err(|e: ParseError| e.rebase(input, rules))?;
+            destinations.extend(
+                rules[1..rules.len() - 1]
+                    .split(',')
+                    .filter_map(|rule| rule.rsplit(':').next()),
+            );
+            Ok((name, workflow))
+        })
+        .collect::<Result<_, ParseError>>()?;
+
+    if !workflows.contains_key("in") {
+        return Err(ParseError::at(input, workflows_str, "a workflow named in"));
+    }
+    if let Some(unknown) = destinations
+        .into_iter()
+        .find(|&d| d != "A" && d != "R" && !workflows.contains_key(d))
+    {
+        return Err(ParseError::at(input, unknown, "A, R or a defined workflow"));
     }
 
-    let mut parts = Vec::new();
-    loop {
-        let mut input = String::new();
-        match io::stdin().read_line(&mut input) {
-            Err(e) => panic!("error reading input: {}", e),
-            Ok(0) => break,
-            Ok(_) => {}
-        }
+    let parts = parts_str
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            line.parse().map_err(|e: ParseError| e.rebase(input, line))
+        })
+        .collect::<Result<_, ParseError>>()?;
 
-        let part: Part = input.trim().parse().expect("error parsing part ratings");
-        parts.push(part);
-    }
+    Ok((workflows, parts))
+}
+
+/// The workflows and the parts to sort through them.
+pub struct Parsed {
+    workflows: HashMap<String, Workflow>,
+    parts: Vec<Part>,
+}
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    let (workflows, parts) = parse(input)?;
+    Ok(Parsed { workflows, parts })
+}
 
-    let x: u32 = parts
+pub fn solve_part1(parsed: &Parsed) -> Result<u32, Error> {
+    Ok(parsed
+        .parts
         .iter()
-        .filter(|part| accepted(part, &workflows))
+        .filter(|part| accepted(part, &parsed.workflows))
         .map(|part| part.ratings.values().sum::<u32>())
-        .sum();
-    println!("part 1: {}", x);
+        .sum())
+}
 
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
     let mut invalid: HashMap<String, Vec<(u32, u32)>> = HashMap::new();
     invalid.insert("x".to_string(), Vec::new());
     invalid.insert("m".to_string(), Vec::new());
     invalid.insert("a".to_string(), Vec::new());
     invalid.insert("s".to_string(), Vec::new());
-    println!(
-        "part 2: {}",
-        solve(&mut invalid, &"in".to_string(), &workflows,)
-    );
+    Ok(solve(&mut invalid, "in", &parsed.workflows))
+}
+
+pub fn part1(input: &str) -> Result<u32, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn accepted_parts() {
+        let (workflows, parts) = parse(TEST_INPUT).unwrap();
+        let accepted: Vec<bool> = parts
+            .iter()
+            .map(|part| accepted(part, &workflows))
+            .collect();
+        assert_eq!(accepted, vec![true, false, true, false, true]);
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(19114));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(167409079868000));
+    }
+
+    #[test]
+    fn invalid_workflows() {
+        assert_eq!(
+            part1("in{x<10:qs,R}\n\n{x=1,m=2,a=3,s=4}\n"),
+            Err(ParseError::new(1, 9, "qs", "A, R or a defined workflow").into())
+        );
+        assert_eq!(
+            part1("in{x!10:A,R}\n\n{x=1,m=2,a=3,s=4}\n"),
+            Err(ParseError::new(1, 5, "!10", "< or >").into())
+        );
+    }
 }
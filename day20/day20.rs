@@ -1,16 +1,16 @@
+use common::{Error, ParseError};
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
-use std::io;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum ModuleType {
     FlipFlop(bool),
     Conjunction(HashMap<String, bool>),
     Broadcast,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Module {
     modtype: ModuleType,
     destinations: Vec<String>,
@@ -56,7 +56,7 @@ fn find_cycles(
                                     cycles[i] = Some(n);
                                 }
                             }
-                            _ => panic!(),
+                            _ => unreachable!(),
                         }
                     }
                 }
@@ -70,7 +70,7 @@ fn find_cycles(
 }
 
 fn transmit_pulse(
-    sender: &String,
+    sender: &str,
     recipient: &String,
     pulse: bool,
     modules: &mut HashMap<String, Module>,
@@ -86,7 +86,7 @@ fn transmit_pulse(
                 }
             }
             ModuleType::Conjunction(ref mut inputs) => {
-                inputs.insert(sender.clone(), pulse);
+                inputs.insert(sender.to_string(), pulse);
                 Some(!inputs.values().all(|&saved| saved))
             }
             ModuleType::Broadcast => Some(pulse),
@@ -122,60 +122,48 @@ fn push_button(modules: &mut HashMap<String, Module>) -> (usize, usize) {
     (high_pulse_count, low_pulse_count)
 }
 
-fn reset_modules(modules: &mut HashMap<String, Module>) {
-    for module in modules.values_mut() {
-        match module.modtype {
-            ModuleType::FlipFlop(ref mut on) => *on = false,
-            ModuleType::Conjunction(ref mut inputs) => {
-                for (_, saved) in inputs {
-                    *saved = false;
-                }
-            }
-            _ => {}
-        }
-    }
-}
-
-fn main() {
-    let mut modules: HashMap<String, Module> = io::stdin()
+fn parse(input: &str) -> Result<HashMap<String, Module>, ParseError> {
+    let mut modules: HashMap<String, Module> = input
         .lines()
         .map(|line| {
-            let line = line.unwrap();
-            let (name, destinations) = line.split_once(" -> ").expect("error parsing input");
+            let (name, destinations) = line
+                .split_once(" -> ")
+                .ok_or_else(|| ParseError::at(input, line, "\"<module> -> <destinations>\""))?;
             let destinations: Vec<String> = destinations
                 .split(", ")
                 .map(|destination| destination.to_string())
                 .collect();
-            let (name, modtype) = match name {
-                "broadcaster" => (name, ModuleType::Broadcast),
-                _ => (
-                    &name[1..],
-                    if name.starts_with("%") {
-                        ModuleType::FlipFlop(false)
-                    } else if name.starts_with("&") {
-                        ModuleType::Conjunction(HashMap::new())
-                    } else {
-                        panic!("invalid module")
-                    },
-                ),
+            let (name, modtype) = if name == "broadcaster" {
+                (name, ModuleType::Broadcast)
+            } else if let Some(name) = name.strip_prefix('%') {
+                (name, ModuleType::FlipFlop(false))
+            } else if let Some(name) = name.strip_prefix('&') {
+                (name, ModuleType::Conjunction(HashMap::new()))
+            } else {
+                return Err(ParseError::at(
+                    input,
+                    name,
+                    "broadcaster, %<flip-flop> or &<conjunction>",
+                ));
             };
 
-            (
+            Ok((
                 name.to_string(),
                 Module {
                     modtype,
                     destinations,
                 },
-            )
+            ))
         })
-        .collect();
+        .collect::<Result<_, ParseError>>()?;
+
+    if !modules.contains_key("broadcaster") {
+        return Err(ParseError::end_of(input, "a broadcaster module"));
+    }
 
     let conjunctions: HashSet<String> = modules
         .iter()
-        .filter(|(_, module)| match &module.modtype {
-            ModuleType::Conjunction(_) => true,
-            _ => false,
-        })
+        .filter(|(_, module)| matches!(&module.modtype, ModuleType::Conjunction(_)))
         .map(|(name, _)| name.clone())
         .collect();
     let mut conjunction_inputs: HashMap<String, Vec<String>> = HashMap::new();
@@ -184,7 +172,7 @@ fn main() {
             if conjunctions.contains(destination) {
                 conjunction_inputs
                     .entry(destination.clone())
-                    .or_insert(Vec::new())
+                    .or_default()
                     .push(name.clone());
             }
         }
@@ -201,6 +189,20 @@ fn main() {
         }
     }
 
+    Ok(modules)
+}
+
+/// The modules in their initial state, which each part pushes the button on
+/// its own copy of.
+pub struct Parsed(HashMap<String, Module>);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    let mut modules = parsed.0.clone();
+
     let mut high_count = 0;
     let mut low_count = 0;
     for _ in 0..1000 {
@@ -209,26 +211,82 @@ fn main() {
         low_count += lc;
     }
 
-    println!("part 1: {}", high_count * low_count);
+    Ok(high_count * low_count)
+}
 
-    reset_modules(&mut modules);
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let mut modules = parsed.0.clone();
 
-    let rx_input_inputs: Vec<String> = modules
-        .iter()
-        .filter(|(_, module)| module.destinations.contains(&"rx".to_string()))
-        .map(|(_, module)| match &module.modtype {
-            ModuleType::Conjunction(inputs) => inputs.keys().map(|k| k.clone()),
-            _ => panic!(),
-        })
-        .flatten()
+    // rx is fed by a single conjunction, itself fed by conjunctions
+    let rx_inputs: Vec<&Module> = modules
+        .values()
+        .filter(|module| module.destinations.contains(&"rx".to_string()))
         .collect();
+    let [Module {
+        modtype: ModuleType::Conjunction(inputs),
+        ..
+    }] = rx_inputs[..]
+    else {
+        return Err(Error::no_solution(
+            "rx isn't fed by a single conjunction module",
+        ));
+    };
+    let rx_input_inputs: Vec<String> = inputs.keys().cloned().collect();
+    if rx_input_inputs.iter().any(|name| {
+        !matches!(
+            modules.get(name),
+            Some(Module {
+                modtype: ModuleType::Conjunction(_),
+                ..
+            })
+        )
+    }) {
+        return Err(Error::no_solution(
+            "rx's conjunction isn't fed by conjunction modules",
+        ));
+    }
     let cycles = find_cycles(&rx_input_inputs, &mut modules);
-    println!(
-        "part 2: {}",
-        cycles
-            .iter()
-            .map(|x| x.unwrap())
-            .reduce(|acc, x| (acc * x) / gcd(acc, x))
-            .unwrap()
-    );
+
+    Ok(cycles
+        .iter()
+        .map(|x| x.unwrap())
+        .fold(1, |acc, x| (acc * x) / gcd(acc, x)))
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+    const TEST_INPUT1: &str = include_str!("test_input1");
+
+    #[test]
+    fn single_push() {
+        let mut modules = parse(TEST_INPUT).unwrap();
+        assert_eq!(push_button(&mut modules), (4, 8));
+    }
+
+    #[test]
+    fn part1_test_inputs() {
+        assert_eq!(part1(TEST_INPUT), Ok(32000000));
+        assert_eq!(part1(TEST_INPUT1), Ok(11687500));
+    }
+
+    #[test]
+    fn no_rx() {
+        assert_eq!(
+            part2(TEST_INPUT),
+            Err(Error::no_solution(
+                "rx isn't fed by a single conjunction module"
+            ))
+        );
+    }
 }
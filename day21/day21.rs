@@ -1,87 +1,20 @@
+use common::{Error, Grid, ParseError, Point};
 use std::collections::HashMap;
 use std::collections::VecDeque;
-use std::io;
 
-#[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
-struct Point {
-    x: isize,
-    y: isize,
+fn find_start(map: &Grid<char>) -> Option<Point> {
+    map.position(|&c| c == 'S')
 }
 
-enum Direction {
-    North,
-    South,
-    West,
-    East,
-}
-
-impl Direction {
-    fn next_pos(&self, pos: &Point) -> Point {
-        match self {
-            Self::North => Point {
-                x: pos.x,
-                y: pos.y - 1,
-            },
-            Self::South => Point {
-                x: pos.x,
-                y: pos.y + 1,
-            },
-            Self::West => Point {
-                x: pos.x - 1,
-                y: pos.y,
-            },
-            Self::East => Point {
-                x: pos.x + 1,
-                y: pos.y,
-            },
-        }
-    }
-}
-
-fn find_start(map: &[Vec<char>]) -> Option<Point> {
-    for y in 0..map.len() {
-        for x in 0..map[y].len() {
-            if map[y][x] == 'S' {
-                return Some(Point {
-                    x: x as isize,
-                    y: y as isize,
-                });
-            }
-        }
-    }
-    None
-}
-
-fn main() {
-    let map: Vec<Vec<char>> = io::stdin()
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect();
-
-    let start = find_start(&map).unwrap();
-
+fn reachable_plots(map: &Grid<char>, start: &Point, max_steps: usize) -> usize {
     let mut q = VecDeque::new();
-    q.push_back((start, 0));
+    q.push_back((*start, 0));
 
     let mut visited: HashMap<Point, usize> = HashMap::new();
 
-    let max_steps = 64;
-
     while let Some((p, steps)) = q.pop_front() {
-        // let y_len = map.len() as isize;
-        // let y = if p.y < 0 {
-        //     (y_len + (p.y % y_len) - 1) as usize
-        // } else {
-        //     (p.y % y_len) as usize
-        // };
-        // let x_len = map[y].len() as isize;
-        // let x = if p.x < 0 {
-        //     (x_len + (p.x % x_len) - 1) as usize
-        // } else {
-        //     (p.x % x_len) as usize
-        // };
-        // if map[y][x] == '#' {
-        if map[p.y as usize][p.x as usize] == '#' {
+        // the map repeats infinitely in every direction
+        if *map.get_wrapped(p) == '#' {
             continue;
         }
 
@@ -94,19 +27,157 @@ fn main() {
             continue;
         }
 
-        for dir in [
-            Direction::North,
-            Direction::South,
-            Direction::West,
-            Direction::East,
-        ] {
-            let next_pos = dir.next_pos(&p);
+        for next_pos in p.neighbours4() {
             q.push_back((next_pos, steps + 1));
         }
     }
 
-    println!(
-        "part 1: {}",
-        visited.iter().filter(|(_, &steps)| steps % 2 == 0).count()
-    );
+    visited
+        .values()
+        .filter(|&steps| steps % 2 == max_steps % 2)
+        .count()
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// periods of the map sampled before giving up on the extrapolation
+const MAX_PERIODS: usize = 12;
+
+// Once the reachable area covers a few copies of the map, walking a period more steps
+// adds a ring of map copies to the diamond, the period being a multiple of both the
+// width and the height so that the ring has the same shape each time. The number of
+// reachable plots then grows quadratically with the number of periods walked. That
+// growth is assumed to have set in once four consecutive second differences of the
+// samples are equal, the fourth one checking the fit made from the first three.
+// Sampling goes on for at most `max_periods`, unless `max_steps` comes first.
+fn reachable_plots_extrapolated(
+    map: &Grid<char>,
+    start: &Point,
+    max_steps: usize,
+    max_periods: usize,
+) -> Result<usize, Error> {
+    let size = map.width() / gcd(map.width(), map.height()) * map.height();
+    let n = max_steps / size;
+    let remainder = max_steps % size;
+
+    let second_difference =
+        |samples: &[i64], i: usize| samples[i + 2] - 2 * samples[i + 1] + samples[i];
+
+    let mut samples: Vec<i64> = Vec::new();
+    for i in 0..=n.min(max_periods) {
+        samples.push(reachable_plots(map, start, remainder + i * size) as i64);
+
+        let len = samples.len();
+        if len >= 6
+            && (len - 6..len - 3)
+                .all(|i| second_difference(&samples, i) == second_difference(&samples, i + 1))
+        {
+            // Newton forward differences from the last three samples
+            let base = len - 3;
+            let x = (n - base) as i64;
+            let d1 = samples[base + 1] - samples[base];
+            let d2 = second_difference(&samples, base);
+
+            return Ok((samples[base] + x * d1 + x * (x - 1) / 2 * d2) as usize);
+        }
+    }
+
+    if samples.len() == n + 1 {
+        return Ok(*samples.last().unwrap() as usize);
+    }
+    Err(Error::NoSolution(format!(
+        "the reachable plots don't grow quadratically within {} periods of the map",
+        max_periods
+    )))
+}
+
+fn parse(input: &str) -> Result<Grid<char>, ParseError> {
+    let map = Grid::parse(input, "a garden plot, rock or the start", |c| {
+        ".#S".contains(c).then_some(c)
+    })?;
+    if find_start(&map).is_none() {
+        return Err(ParseError::end_of(input, "a start tile"));
+    }
+
+    Ok(map)
+}
+
+/// The garden map with its start tile.
+pub struct Parsed {
+    map: Grid<char>,
+    start: Point,
+}
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    let map = parse(input)?;
+    let start = find_start(&map).unwrap();
+    Ok(Parsed { map, start })
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    Ok(reachable_plots(&parsed.map, &parsed.start, 64))
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    reachable_plots_extrapolated(&parsed.map, &parsed.start, 26501365, MAX_PERIODS)
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn reachable() {
+        let map = parse(TEST_INPUT).unwrap();
+        let start = find_start(&map).unwrap();
+        assert_eq!(reachable_plots(&map, &start, 6), 16);
+    }
+
+    #[test]
+    fn extrapolation_matches_bfs() {
+        let map = parse(TEST_INPUT).unwrap();
+        let start = find_start(&map).unwrap();
+        for (steps, plots) in [(6, 16), (10, 50), (50, 1594), (100, 6536), (500, 167004)] {
+            assert_eq!(reachable_plots(&map, &start, steps), plots);
+            assert_eq!(
+                reachable_plots_extrapolated(&map, &start, steps, MAX_PERIODS),
+                Ok(plots)
+            );
+        }
+        assert_eq!(
+            reachable_plots_extrapolated(&map, &start, 500, 4),
+            Err(Error::no_solution(
+                "the reachable plots don't grow quadratically within 4 periods of the map"
+            ))
+        );
+    }
+
+    #[test]
+    fn extrapolation_on_rectangular_map() {
+        let map = parse(".#...\n..S..\n...#.\n").unwrap();
+        let start = find_start(&map).unwrap();
+        for (steps, plots) in [(100, 8841), (301, 79044)] {
+            assert_eq!(reachable_plots(&map, &start, steps), plots);
+            assert_eq!(
+                reachable_plots_extrapolated(&map, &start, steps, MAX_PERIODS),
+                Ok(plots)
+            );
+        }
+    }
 }
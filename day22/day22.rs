@@ -1,7 +1,7 @@
+use common::{parse_token, Error, ParseError};
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
-use std::io;
 use std::str::FromStr;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -21,20 +21,21 @@ impl Point {
     }
 }
 
-#[derive(Debug)]
-struct ParsePointError;
-
 impl FromStr for Point {
-    type Err = ParsePointError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (x, yz) = s.split_once(',').ok_or(ParsePointError)?;
-        let (y, z) = yz.split_once(',').ok_or(ParsePointError)?;
+        let (x, yz) = s
+            .split_once(',')
+            .ok_or_else(|| ParseError::at(s, s, "\"<x>,<y>,<z>\""))?;
+        let (y, z) = yz
+            .split_once(',')
+            .ok_or_else(|| ParseError::at(s, s, "\"<x>,<y>,<z>\""))?;
 
         Ok(Point {
-            x: x.parse().map_err(|_| ParsePointError)?,
-            y: y.parse().map_err(|_| ParsePointError)?,
-            z: z.parse().map_err(|_| ParsePointError)?,
+            x: parse_token(s, x, "a coordinate")?,
+            y: parse_token(s, y, "a coordinate")?,
+            z: parse_token(s, z, "a coordinate")?,
         })
     }
 }
@@ -67,18 +68,25 @@ impl Brick {
     }
 }
 
-#[derive(Debug)]
-struct ParseBrickError;
-
 impl FromStr for Brick {
-    type Err = ParseBrickError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (start, end) = s.split_once('~').ok_or(ParseBrickError)?;
+        let (start, end) = s
+            .split_once('~')
+            .ok_or_else(|| ParseError::at(s, s, "\"<start>~<end>\""))?;
         let brick = Brick {
-            start: start.parse().map_err(|_| ParseBrickError)?,
-            end: end.parse().map_err(|_| ParseBrickError)?,
+            start: start.parse().map_err(|e: ParseError| e.rebase(s, start))?,
+            end: end.parse().map_err(|e: ParseError| e.rebase(s, end))?,
         };
+        let axes = [
+            brick.start.x != brick.end.x,
+            brick.start.y != brick.end.y,
+            brick.start.z != brick.end.z,
+        ];
+        if axes.iter().filter(|&&differs| differs).count() > 1 {
+            return Err(ParseError::at(s, end, "an end aligned with the start"));
+        }
         if brick.start.x > brick.end.x || brick.start.y > brick.end.y || brick.start.z > brick.end.z
         {
             Ok(Brick {
@@ -101,10 +109,7 @@ fn build_state(bricks: &[Brick]) -> State {
         let dir = brick.direction();
 
         loop {
-            state
-                .entry((pos.x, pos.y))
-                .or_insert(Vec::new())
-                .push((pos.z, i));
+            state.entry((pos.x, pos.y)).or_default().push((pos.z, i));
             if pos == brick.end {
                 break;
             }
@@ -193,21 +198,21 @@ fn would_fall(
     fell.len()
 }
 
-fn main() {
-    let mut bricks: Vec<Brick> = io::stdin()
+fn parse(input: &str) -> Result<Vec<Brick>, ParseError> {
+    input
         .lines()
-        .map(|line| {
-            line.expect("error reading input")
-                .parse()
-                .expect("error parsing brick")
-        })
-        .collect();
+        .map(|line| line.parse().map_err(|e: ParseError| e.rebase(input, line)))
+        .collect()
+}
 
-    make_bricks_fall(&mut bricks);
+// Lets the bricks settle and returns, for each brick, the bricks it supports and
+// the bricks it is supported by.
+fn supports(bricks: &mut [Brick]) -> (Vec<HashSet<usize>>, Vec<HashSet<usize>>) {
+    make_bricks_fall(bricks);
 
     let mut supported_by: Vec<HashSet<usize>> = vec![HashSet::new(); bricks.len()];
     let mut supporting: Vec<HashSet<usize>> = vec![HashSet::new(); bricks.len()];
-    let state = build_state(&bricks);
+    let state = build_state(bricks);
 
     for (i, brick) in bricks.iter().enumerate() {
         let (base_start, base_end) = brick.base();
@@ -239,22 +244,72 @@ fn main() {
         }
     }
 
-    println!(
-        "part 1: {}",
-        supporting
-            .iter()
-            .enumerate()
-            .filter(|(_, supported)| supported
+    (supporting, supported_by)
+}
+
+/// The bricks as they were snapshotted, before they settle.
+pub struct Parsed(Vec<Brick>);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    let (supporting, supported_by) = supports(&mut parsed.0.clone());
+
+    Ok(supporting
+        .iter()
+        .filter(|supported| {
+            supported
                 .iter()
-                .all(|&supported| supported_by[supported].len() > 1))
-            .count()
-    );
-
-    println!(
-        "part 2: {}",
-        (0..bricks.len())
-            .map(|i| would_fall(i, &supporting, &supported_by))
-            .map(|n| n - 1)
-            .sum::<usize>()
-    );
+                .all(|&supported| supported_by[supported].len() > 1)
+        })
+        .count())
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let mut bricks = parsed.0.clone();
+    let (supporting, supported_by) = supports(&mut bricks);
+
+    Ok((0..bricks.len())
+        .map(|i| would_fall(i, &supporting, &supported_by))
+        .map(|n| n - 1)
+        .sum::<usize>())
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(5));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(7));
+    }
+
+    #[test]
+    fn invalid_bricks() {
+        assert_eq!(
+            part1("1,0,1~1,2,1\n0,0,2~2,x,2\n"),
+            Err(ParseError::new(2, 9, "x", "a coordinate").into())
+        );
+        assert_eq!(
+            part1("1,0,1~1,2,2\n"),
+            Err(ParseError::new(1, 7, "1,2,2", "an end aligned with the start").into())
+        );
+    }
 }
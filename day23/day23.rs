@@ -1,46 +1,15 @@
+use common::{Direction, Error, Grid, ParseError, Point};
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
-use std::io;
 
-#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Debug)]
-struct Point {
-    x: isize,
-    y: isize,
-}
-
-impl Point {
-    fn next(&self, direction: &Point) -> Point {
-        Point {
-            x: self.x + direction.x,
-            y: self.y + direction.y,
-        }
-    }
-}
-
-const UP: Point = Point { x: 0, y: -1 };
-const DOWN: Point = Point { x: 0, y: 1 };
-const LEFT: Point = Point { x: -1, y: 0 };
-const RIGHT: Point = Point { x: 1, y: 0 };
-
-fn within_bounds(p: &Point, map: &[Vec<char>]) -> bool {
-    p.y >= 0 && (p.y as usize) < map.len() && p.x >= 0 && (p.x as usize) < map[p.y as usize].len()
-}
-
-fn neighbours(pos: &Point, map: &[Vec<char>]) -> Vec<Point> {
-    [UP, DOWN, LEFT, RIGHT]
-        .iter()
-        .map(|dir| pos.next(dir))
-        .filter(|adj| within_bounds(&adj, map) && map[adj.y as usize][adj.x as usize] != '#')
+fn neighbours(pos: &Point, map: &Grid<char>) -> Vec<Point> {
+    map.neighbours4(*pos)
+        .filter(|&adj| map[adj] != '#')
         .collect()
 }
 
-fn dfs(
-    pos: &Point,
-    goal: &Point,
-    map: &[Vec<char>],
-    visited: &mut HashSet<Point>,
-) -> Option<usize> {
+fn dfs(pos: &Point, goal: &Point, map: &Grid<char>, visited: &mut HashSet<Point>) -> Option<usize> {
     if pos == goal {
         return Some(visited.len());
     }
@@ -50,16 +19,19 @@ fn dfs(
     }
     visited.insert(*pos);
 
-    let n = match map[pos.y as usize][pos.x as usize] {
-        '^' => dfs(&pos.next(&UP), goal, map, visited),
-        'v' => dfs(&pos.next(&DOWN), goal, map, visited),
-        '<' => dfs(&pos.next(&LEFT), goal, map, visited),
-        '>' => dfs(&pos.next(&RIGHT), goal, map, visited),
-        _ => neighbours(pos, map)
-            .iter()
-            .filter_map(|p| dfs(p, goal, map, visited))
-            .max(),
+    let next = match map[*pos] {
+        '^' => vec![pos.step(Direction::Up)],
+        'v' => vec![pos.step(Direction::Down)],
+        '<' => vec![pos.step(Direction::Left)],
+        '>' => vec![pos.step(Direction::Right)],
+        _ => neighbours(pos, map),
     };
+    let n = next
+        .iter()
+        // a slope may point off the map or into the forest
+        .filter(|&&p| map.get(p).is_some_and(|&c| c != '#'))
+        .filter_map(|p| dfs(p, goal, map, visited))
+        .max();
 
     visited.remove(pos);
 
@@ -91,14 +63,14 @@ fn dfs2(
 
     visited.remove(pos);
 
-    n.and_then(|n| Some(distance + n))
+    n.map(|n| distance + n)
 }
 
 fn contract(
     u: &Point,
     start: &Point,
     goal: &Point,
-    map: &[Vec<char>],
+    map: &Grid<char>,
     graph: &mut HashMap<Point, Vec<(Point, usize)>>,
 ) {
     let mut q = VecDeque::new();
@@ -131,31 +103,21 @@ fn contract(
 fn contracted_graph(
     start: &Point,
     goal: &Point,
-    map: &[Vec<char>],
+    map: &Grid<char>,
 ) -> HashMap<Point, Vec<(Point, usize)>> {
     let junctions: Vec<Point> = map
         .iter()
-        .enumerate()
-        .map(|(y, row)| {
-            row.iter()
-                .enumerate()
-                .filter(|(_, &c)| c != '#')
-                .map(move |(x, _)| Point {
-                    x: x as isize,
-                    y: y as isize,
-                })
-                .filter(|p| neighbours(p, &map).len() > 2)
-        })
-        .flatten()
+        .filter(|(p, &c)| c != '#' && neighbours(p, map).len() > 2)
+        .map(|(p, _)| p)
         .collect();
 
     let mut graph = HashMap::new();
 
     for junction in junctions {
-        contract(&junction, &start, &goal, &map, &mut graph);
+        contract(&junction, start, goal, map, &mut graph);
     }
-    contract(&start, &start, &goal, &map, &mut graph);
-    contract(&goal, &start, &goal, &map, &mut graph);
+    contract(start, start, goal, map, &mut graph);
+    contract(goal, start, goal, map, &mut graph);
 
     graph
 }
@@ -168,29 +130,98 @@ fn single_path_tile_index(row: &[char]) -> Option<usize> {
         .next()
 }
 
-fn main() {
-    let map: Vec<Vec<char>> = io::stdin()
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect();
+fn parse(input: &str) -> Result<(Grid<char>, Point, Point), ParseError> {
+    let map = Grid::parse(input, "a path, forest or slope", |c| {
+        "#.^v<>".contains(c).then_some(c)
+    })?;
+
+    let first_line = input.lines().next().unwrap();
+    let last_line = input.lines().last().unwrap();
+    let start = Point::new(
+        single_path_tile_index(map.row(0))
+            .ok_or_else(|| ParseError::at(input, first_line, "a path tile on the first row"))?
+            as isize,
+        0,
+    );
+    let goal = Point::new(
+        single_path_tile_index(map.row(map.height() - 1))
+            .ok_or_else(|| ParseError::at(input, last_line, "a path tile on the last row"))?
+            as isize,
+        map.height() as isize - 1,
+    );
 
-    let start = Point {
-        x: single_path_tile_index(&map[0]).unwrap() as isize,
-        y: 0,
-    };
-    let goal = Point {
-        x: single_path_tile_index(map.last().unwrap()).unwrap() as isize,
-        y: (map.len() - 1) as isize,
-    };
+    Ok((map, start, goal))
+}
 
-    println!(
-        "part 1: {}",
-        dfs(&start, &goal, &map, &mut HashSet::new()).unwrap()
-    );
+/// The hiking trails map with its start and goal tiles.
+pub struct Parsed {
+    map: Grid<char>,
+    start: Point,
+    goal: Point,
+}
 
-    let graph = contracted_graph(&start, &goal, &map);
-    println!(
-        "part 2: {}",
-        dfs2(&start, 0, &goal, &graph, &mut HashSet::new()).unwrap()
-    );
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    let (map, start, goal) = parse(input)?;
+    Ok(Parsed { map, start, goal })
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    let Parsed { map, start, goal } = parsed;
+
+    dfs(start, goal, map, &mut HashSet::new())
+        .ok_or_else(|| Error::no_solution("no path leads from the start to the goal"))
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<usize, Error> {
+    let Parsed { map, start, goal } = parsed;
+
+    let graph = contracted_graph(start, goal, map);
+    dfs2(start, 0, goal, &graph, &mut HashSet::new())
+        .ok_or_else(|| Error::no_solution("no path leads from the start to the goal"))
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(94));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(154));
+    }
+
+    #[test]
+    fn walled_off_goal() {
+        let input = "#.#\n#.#\n###\n#.#\n";
+        let no_path = Err(Error::no_solution(
+            "no path leads from the start to the goal",
+        ));
+        assert_eq!(part1(input), no_path);
+        assert_eq!(part2(input), no_path);
+    }
+
+    #[test]
+    fn dead_end_slopes() {
+        let no_path = Err(Error::no_solution(
+            "no path leads from the start to the goal",
+        ));
+        // off the map, then into the forest
+        assert_eq!(part1("#^#\n#.#\n#.#\n"), no_path);
+        assert_eq!(part1("#.##\n#>#.\n##..\n###.\n"), no_path);
+        assert_eq!(part2("#^#\n#.#\n#.#\n"), Ok(2));
+    }
 }
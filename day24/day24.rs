@@ -1,26 +1,108 @@
-use std::io;
+use common::{parse_token, Error, ParseError};
+use std::ops::{Add, Mul, Sub};
 use std::str::FromStr;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 struct Vec3 {
-    x: i64,
-    y: i64,
-    z: i64,
+    x: i128,
+    y: i128,
+    z: i128,
+}
+
+impl Vec3 {
+    fn cross(&self, other: &Self) -> Self {
+        Vec3 {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
+    fn dot(&self, other: &Self) -> i128 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    fn is_zero(&self) -> bool {
+        self.x == 0 && self.y == 0 && self.z == 0
+    }
+
+    // Smallest integer vector with the same direction.
+    fn reduced(&self) -> Self {
+        let d = gcd(gcd(self.x, self.y), self.z);
+        if d == 0 {
+            *self
+        } else {
+            Vec3 {
+                x: self.x / d,
+                y: self.y / d,
+                z: self.z / d,
+            }
+        }
+    }
+
+    fn div_exact(&self, d: i128) -> Option<Self> {
+        if d == 0 || self.x % d != 0 || self.y % d != 0 || self.z % d != 0 {
+            None
+        } else {
+            Some(Vec3 {
+                x: self.x / d,
+                y: self.y / d,
+                z: self.z / d,
+            })
+        }
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Vec3 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Vec3 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
 }
 
-#[derive(Debug)]
-struct ParseVec3Error;
+impl Mul<i128> for Vec3 {
+    type Output = Self;
+
+    fn mul(self, t: i128) -> Self {
+        Vec3 {
+            x: self.x * t,
+            y: self.y * t,
+            z: self.z * t,
+        }
+    }
+}
 
 impl FromStr for Vec3 {
-    type Err = ParseVec3Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (x, yz) = s.split_once(',').ok_or(ParseVec3Error)?;
-        let (y, z) = yz.split_once(',').ok_or(ParseVec3Error)?;
+        let (x, yz) = s
+            .split_once(',')
+            .ok_or_else(|| ParseError::at(s, s, "\"<x>, <y>, <z>\""))?;
+        let (y, z) = yz
+            .split_once(',')
+            .ok_or_else(|| ParseError::at(s, s, "\"<x>, <y>, <z>\""))?;
         Ok(Vec3 {
-            x: x.trim().parse().map_err(|_| ParseVec3Error)?,
-            y: y.trim().parse().map_err(|_| ParseVec3Error)?,
-            z: z.trim().parse().map_err(|_| ParseVec3Error)?,
+            x: parse_token(s, x.trim(), "an integer")?,
+            y: parse_token(s, y.trim(), "an integer")?,
+            z: parse_token(s, z.trim(), "an integer")?,
         })
     }
 }
@@ -29,12 +111,8 @@ type Point = Vec3;
 type Velocity = Vec3;
 
 impl Point {
-    fn advance(&self, t: i64, velocity: &Velocity) -> Self {
-        Point {
-            x: self.x + (t * velocity.x),
-            y: self.y + (t * velocity.y),
-            z: self.z + (t * velocity.z),
-        }
+    fn advance(&self, t: i128, velocity: &Velocity) -> Self {
+        *self + *velocity * t
     }
 }
 
@@ -45,70 +123,285 @@ struct Hailstone {
 }
 
 impl Hailstone {
-    fn can_reach(&self, x: f64) -> bool {
-        (x >= self.position.x as f64 && self.velocity.x >= 0)
-            || (x <= self.position.x as f64 && self.velocity.x <= 0)
+    fn collides_with(&self, other: &Hailstone) -> bool {
+        // self.position + t * self.velocity == other.position + t * other.velocity, t >= 0
+        let dp = self.position - other.position;
+        let dv = other.velocity - self.velocity;
+        if dv.is_zero() {
+            dp.is_zero()
+        } else {
+            dp.cross(&dv).is_zero() && dp.dot(&dv) >= 0
+        }
     }
 }
 
-#[derive(Debug)]
-struct ParseHailstoneError;
-
 impl FromStr for Hailstone {
-    type Err = ParseHailstoneError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (p, v) = s.split_once('@').ok_or(ParseHailstoneError)?;
+        let (p, v) = s
+            .split_once('@')
+            .ok_or_else(|| ParseError::at(s, s, "\"<position> @ <velocity>\""))?;
+        let (p, v) = (p.trim(), v.trim());
         Ok(Hailstone {
-            position: p.trim().parse().map_err(|_| ParseHailstoneError)?,
-            velocity: v.trim().parse().map_err(|_| ParseHailstoneError)?,
+            position: p.parse().map_err(|e: ParseError| e.rebase(s, p))?,
+            velocity: v.parse().map_err(|e: ParseError| e.rebase(s, v))?,
         })
     }
 }
 
-fn line_eq(hailstone: &Hailstone) -> (f64, f64) {
-    let p1 = hailstone.position;
-    let p2 = hailstone.position.advance(1, &hailstone.velocity);
+fn gcd(a: i128, b: i128) -> i128 {
+    let mut a = a.abs();
+    let mut b = b.abs();
 
-    // y = ax + b
-    let slope = (p2.y - p1.y) as f64 / (p2.x - p1.x) as f64;
-    let b = p1.y as f64 - slope * p1.x as f64;
+    while b != 0 {
+        let t = b;
+        b = a % b;
+        a = t;
+    }
 
-    (slope, b)
+    a
 }
 
-fn within_bounds(a: f64, min: f64, max: f64) -> bool {
-    min <= a && a <= max
-}
+// Integer time at which the hailstone crosses the line going through the origin
+// with direction `dir`.
+fn crossing_time(hailstone: &Hailstone, dir: &Vec3) -> Option<i128> {
+    // (position + t * velocity) x dir == 0
+    let a = hailstone.position.cross(dir);
+    let b = hailstone.velocity.cross(dir);
+    let (num, den) = [(a.x, b.x), (a.y, b.y), (a.z, b.z)]
+        .into_iter()
+        .find(|&(_, den)| den != 0)?;
+    if num % den != 0 {
+        return None;
+    }
+    let t = -num / den;
 
-fn main() {
-    let hailstones: Vec<Hailstone> = io::stdin()
-        .lines()
-        .map(|line| line.unwrap().parse().expect("error parsing hailstone"))
-        .collect();
+    if (a + b * t).is_zero() {
+        Some(t)
+    } else {
+        None
+    }
+}
 
-    // let (min_coord, max_coord) = (7f64, 27f64);
-    let (min_coord, max_coord) = (200000000000000f64, 400000000000000f64);
+fn rock_trajectory(hailstones: &[Hailstone]) -> Option<Hailstone> {
+    let reference = hailstones.first()?;
 
-    let intersections = hailstones
+    // In the reference hailstone's frame, the rock goes through the origin, so its
+    // path lies in every plane containing the origin and another hailstone's path.
+    let relative: Vec<Hailstone> = hailstones
         .iter()
-        .enumerate()
-        .map(|(i, h1)| {
-            hailstones.iter().skip(i + 1).filter(move |h2| {
-                let (a1, b1) = line_eq(h1);
-                let (a2, b2) = line_eq(h2);
-
-                let x = (b2 - b1) / (a1 - a2);
-                let y = a1 * x + b1;
-
-                h1.can_reach(x)
-                    && h2.can_reach(x)
-                    && within_bounds(x, min_coord, max_coord)
-                    && within_bounds(y, min_coord, max_coord)
-            })
+        .skip(1)
+        .map(|h| Hailstone {
+            position: h.position - reference.position,
+            velocity: h.velocity - reference.velocity,
         })
-        .flatten()
-        .count();
+        .collect();
+
+    for (i, h1) in relative.iter().enumerate() {
+        for h2 in relative.iter().skip(i + 1) {
+            let normal1 = h1.position.cross(&h1.velocity);
+            let normal2 = h2.position.cross(&h2.velocity);
+            let dir = normal1.cross(&normal2).reduced();
+            if dir.is_zero() {
+                continue;
+            }
+
+            let (Some(t1), Some(t2)) = (crossing_time(h1, &dir), crossing_time(h2, &dir)) else {
+                continue;
+            };
+            if t1 == t2 {
+                continue;
+            }
+
+            let p1 = h1.position.advance(t1, &h1.velocity);
+            let p2 = h2.position.advance(t2, &h2.velocity);
+            let Some(velocity) = (p2 - p1).div_exact(t2 - t1) else {
+                continue;
+            };
+
+            let rock = Hailstone {
+                position: p1 - velocity * t1 + reference.position,
+                velocity: velocity + reference.velocity,
+            };
+            if hailstones.iter().all(|h| rock.collides_with(h)) {
+                return Some(rock);
+            }
+        }
+    }
+
+    None
+}
 
-    println!("part 1: {}", intersections);
+#[derive(Debug, Eq, PartialEq)]
+enum PathCrossing {
+    Future { within_test_area: bool },
+    Past,
+    Parallel,
+    Collinear,
+}
+
+fn cross_2d(a: &Vec3, b: &Vec3) -> i128 {
+    a.x * b.y - a.y * b.x
+}
+
+// Checks min <= num / den <= max without dividing.
+fn within_bounds(num: i128, den: i128, min: i128, max: i128) -> bool {
+    let (num, den) = if den < 0 { (-num, -den) } else { (num, den) };
+    min * den <= num && num <= max * den
+}
+
+// Where the paths of two hailstones cross, ignoring the Z axis.
+fn path_crossing(h1: &Hailstone, h2: &Hailstone, min: i128, max: i128) -> PathCrossing {
+    let dp = h2.position - h1.position;
+    let det = cross_2d(&h1.velocity, &h2.velocity);
+
+    if det == 0 {
+        return if cross_2d(&dp, &h1.velocity) == 0 {
+            PathCrossing::Collinear
+        } else {
+            PathCrossing::Parallel
+        };
+    }
+
+    // h1.position + t1 * h1.velocity == h2.position + t2 * h2.velocity
+    let t1_num = cross_2d(&dp, &h2.velocity);
+    let t2_num = cross_2d(&dp, &h1.velocity);
+    if t1_num.signum() * det.signum() < 0 || t2_num.signum() * det.signum() < 0 {
+        return PathCrossing::Past;
+    }
+
+    let x = h1.position.x * det + t1_num * h1.velocity.x;
+    let y = h1.position.y * det + t1_num * h1.velocity.y;
+
+    PathCrossing::Future {
+        within_test_area: within_bounds(x, det, min, max) && within_bounds(y, det, min, max),
+    }
+}
+
+#[derive(Debug, Default)]
+struct CrossingCounts {
+    inside: usize,
+    outside: usize,
+    past: usize,
+    parallel: usize,
+    collinear: usize,
+}
+
+fn count_path_crossings(hailstones: &[Hailstone], min: i128, max: i128) -> CrossingCounts {
+    let mut counts = CrossingCounts::default();
+
+    for (i, h1) in hailstones.iter().enumerate() {
+        for h2 in hailstones.iter().skip(i + 1) {
+            match path_crossing(h1, h2, min, max) {
+                PathCrossing::Future {
+                    within_test_area: true,
+                } => counts.inside += 1,
+                PathCrossing::Future {
+                    within_test_area: false,
+                } => counts.outside += 1,
+                PathCrossing::Past => counts.past += 1,
+                PathCrossing::Parallel => counts.parallel += 1,
+                PathCrossing::Collinear => counts.collinear += 1,
+            }
+        }
+    }
+
+    counts
+}
+
+fn parse(input: &str) -> Result<Vec<Hailstone>, ParseError> {
+    input
+        .lines()
+        .map(|line| line.parse().map_err(|e: ParseError| e.rebase(input, line)))
+        .collect()
+}
+
+const TEST_AREA: (i128, i128) = (200000000000000, 400000000000000);
+
+/// The hailstones' positions and velocities.
+pub struct Parsed(Vec<Hailstone>);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    let (min_coord, max_coord) = TEST_AREA;
+    Ok(count_path_crossings(&parsed.0, min_coord, max_coord).inside)
+}
+
+pub fn solve_part2(parsed: &Parsed) -> Result<i128, Error> {
+    let rock = rock_trajectory(&parsed.0)
+        .ok_or_else(|| Error::no_solution("no rock trajectory hits every hailstone"))?;
+    Ok(rock.position.x + rock.position.y + rock.position.z)
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn part2(input: &str) -> Result<i128, Error> {
+    solve_part2(&parse_input(input)?)
+}
+
+pub fn details(input: &str) -> Result<String, Error> {
+    let (min_coord, max_coord) = TEST_AREA;
+    let counts = count_path_crossings(&parse(input)?, min_coord, max_coord);
+    Ok(format!(
+        "crossing inside test area: {}, outside test area: {}, in the past: {}, parallel: {}, collinear: {}",
+        counts.inside, counts.outside, counts.past, counts.parallel, counts.collinear
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn crossings() {
+        let counts = count_path_crossings(&parse(TEST_INPUT).unwrap(), 7, 27);
+        assert_eq!(
+            (
+                counts.inside,
+                counts.outside,
+                counts.past,
+                counts.parallel,
+                counts.collinear
+            ),
+            (2, 3, 4, 1, 0)
+        );
+    }
+
+    #[test]
+    fn vertical_and_collinear_paths() {
+        let hailstones = parse("0, 0, 0 @ 0, 1, 0\n-5, 5, 0 @ 1, 0, 0\n0, 8, 0 @ 0, 2, 0").unwrap();
+        assert_eq!(
+            path_crossing(&hailstones[0], &hailstones[1], 0, 10),
+            PathCrossing::Future {
+                within_test_area: true
+            }
+        );
+        assert_eq!(
+            path_crossing(&hailstones[0], &hailstones[2], 0, 10),
+            PathCrossing::Collinear
+        );
+    }
+
+    #[test]
+    fn part2_test_input() {
+        assert_eq!(part2(TEST_INPUT), Ok(47));
+    }
+
+    #[test]
+    fn no_trajectory() {
+        assert_eq!(
+            part2("1, 2, 3 @ 1, 0, 0\n"),
+            Err(Error::no_solution(
+                "no rock trajectory hits every hailstone"
+            ))
+        );
+    }
 }
@@ -1,61 +1,307 @@
+use common::{Error, ParseError};
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::io;
-
-fn outward_edges(
-    node: &String,
-    group: &HashSet<&String>,
-    graph: &HashMap<String, HashSet<String>>,
-) -> usize {
-    graph
-        .get(node)
-        .unwrap()
+use std::collections::VecDeque;
+
+struct Cut {
+    edges: Vec<(String, String)>,
+    components: (Vec<String>, Vec<String>),
+}
+
+// Shortest augmenting path from `source` to `sink` in the residual graph, as the
+// predecessor of each node on the path.
+fn augmenting_path(
+    source: usize,
+    sink: usize,
+    adj: &[Vec<usize>],
+    flow: &HashMap<(usize, usize), i32>,
+) -> Option<Vec<Option<usize>>> {
+    let mut prev = vec![None; adj.len()];
+    let mut visited = vec![false; adj.len()];
+    let mut q = VecDeque::new();
+
+    visited[source] = true;
+    q.push_back(source);
+
+    while let Some(u) = q.pop_front() {
+        if u == sink {
+            return Some(prev);
+        }
+
+        for &v in &adj[u] {
+            let residual = 1 - flow.get(&(u, v)).unwrap_or(&0);
+            if !visited[v] && residual > 0 {
+                visited[v] = true;
+                prev[v] = Some(u);
+                q.push_back(v);
+            }
+        }
+    }
+
+    None
+}
+
+// Edmonds-Karp on the graph with unit capacity edges. Returns the max flow value
+// and the set of nodes still reachable from `source` in the residual graph.
+fn max_flow(source: usize, sink: usize, adj: &[Vec<usize>]) -> (usize, Vec<bool>) {
+    let mut flow: HashMap<(usize, usize), i32> = HashMap::new();
+    let mut total = 0;
+
+    while let Some(prev) = augmenting_path(source, sink, adj, &flow) {
+        let mut v = sink;
+        while let Some(u) = prev[v] {
+            *flow.entry((u, v)).or_insert(0) += 1;
+            *flow.entry((v, u)).or_insert(0) -= 1;
+            v = u;
+        }
+        total += 1;
+    }
+
+    let mut reachable = vec![false; adj.len()];
+    let mut q = VecDeque::new();
+    reachable[source] = true;
+    q.push_back(source);
+    while let Some(u) = q.pop_front() {
+        for &v in &adj[u] {
+            let residual = 1 - flow.get(&(u, v)).unwrap_or(&0);
+            if !reachable[v] && residual > 0 {
+                reachable[v] = true;
+                q.push_back(v);
+            }
+        }
+    }
+
+    (total, reachable)
+}
+
+// The global minimum cut separates the first node from at least one other node,
+// so it is the smallest of the max flows between the first node and every other.
+fn min_cut(graph: &HashMap<String, HashSet<String>>) -> Option<Cut> {
+    let mut names: Vec<&String> = graph.keys().collect();
+    names.sort();
+    let index: HashMap<&String, usize> = names.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+    let adj: Vec<Vec<usize>> = names
         .iter()
-        .filter(|adj| !group.contains(adj))
-        .count()
+        .map(|&name| {
+            let mut adj: Vec<usize> = graph
+                .get(name)
+                .unwrap()
+                .iter()
+                .map(|adj| *index.get(adj).unwrap())
+                .collect();
+            adj.sort();
+            adj
+        })
+        .collect();
+
+    let (_, source_side) = (1..names.len())
+        .map(|sink| max_flow(0, sink, &adj))
+        .min_by_key(|(flow, _)| *flow)?;
+
+    let mut edges: Vec<(String, String)> = Vec::new();
+    for (u, adj) in adj.iter().enumerate() {
+        for &v in adj {
+            if source_side[u] && !source_side[v] {
+                let (a, b) = if names[u] < names[v] { (u, v) } else { (v, u) };
+                edges.push((names[a].clone(), names[b].clone()));
+            }
+        }
+    }
+    edges.sort();
+
+    let (component1, component2): (Vec<usize>, Vec<usize>) =
+        (0..names.len()).partition(|&u| source_side[u]);
+
+    Some(Cut {
+        edges,
+        components: (
+            component1.iter().map(|&u| names[u].clone()).collect(),
+            component2.iter().map(|&u| names[u].clone()).collect(),
+        ),
+    })
+}
+
+// DOT identifiers are quoted, as component names may be any non-space token.
+fn quoted(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
 }
 
-fn total_outward_edges(
-    group: &HashSet<&String>,
-    graph: &HashMap<String, HashSet<String>>,
-) -> usize {
-    group
+// Graphviz document of the wiring, with each component and the cut edges in their
+// own colour.
+fn to_dot(graph: &HashMap<String, HashSet<String>>, cut: &Cut) -> String {
+    let mut dot = String::from("graph day25 {\n\tnode [style=filled];\n");
+
+    for (component, color) in [
+        (&cut.components.0, "lightblue"),
+        (&cut.components.1, "palegreen"),
+    ] {
+        for node in component {
+            dot += &format!("\t{} [fillcolor={}];\n", quoted(node), color);
+        }
+    }
+
+    let mut edges: Vec<(&String, &String)> = graph
         .iter()
-        .map(|node| outward_edges(node, group, graph))
-        .sum()
+        .flat_map(|(node, adj_list)| adj_list.iter().map(move |adj| (node, adj)))
+        .filter(|(node, adj)| node < adj)
+        .collect();
+    edges.sort();
+
+    for (a, b) in edges {
+        if cut.edges.contains(&(a.clone(), b.clone())) {
+            dot += &format!(
+                "\t{} -- {} [color=red, penwidth=3];\n",
+                quoted(a),
+                quoted(b)
+            );
+        } else {
+            dot += &format!("\t{} -- {};\n", quoted(a), quoted(b));
+        }
+    }
+
+    dot + "}\n"
 }
 
-fn main() {
+fn parse(input: &str) -> Result<HashMap<String, HashSet<String>>, ParseError> {
     let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
 
-    for line in io::stdin().lines() {
-        let line = line.expect("error reading input");
-        let (node, adj_list) = line.split_once(": ").expect("error parsing input");
-        let mut adj_list: HashSet<_> = adj_list.split(' ').map(|s| s.to_string()).collect();
+    for line in input.lines() {
+        let (node, adj_list) = line
+            .split_once(": ")
+            .ok_or_else(|| ParseError::at(input, line, "\"<component>: <components>\""))?;
 
-        for adj in &adj_list {
+        for adj in adj_list.split(' ') {
+            if adj.is_empty() || adj == node {
+                return Err(ParseError::at(input, adj, "another component"));
+            }
             graph
                 .entry(node.to_string())
-                .or_insert(HashSet::new())
+                .or_default()
                 .insert(adj.to_string());
             graph
                 .entry(adj.to_string())
-                .or_insert(HashSet::new())
+                .or_default()
                 .insert(node.to_string());
         }
     }
 
-    let mut group: HashSet<_> = graph.keys().collect();
-    while total_outward_edges(&group, &graph) != 3 {
-        let node = group
+    if graph.is_empty() {
+        return Err(ParseError::end_of(input, "a connection"));
+    }
+
+    Ok(graph)
+}
+
+// The minimum cut, a graph needing two nodes to be cut in two.
+fn cut_in_two(graph: &HashMap<String, HashSet<String>>) -> Result<Cut, Error> {
+    min_cut(graph).ok_or_else(|| Error::no_solution("the diagram has fewer than two components"))
+}
+
+/// The wiring diagram as an undirected graph.
+pub struct Parsed(HashMap<String, HashSet<String>>);
+
+pub fn parse_input(input: &str) -> Result<Parsed, ParseError> {
+    parse(input).map(Parsed)
+}
+
+pub fn solve_part1(parsed: &Parsed) -> Result<usize, Error> {
+    let cut = cut_in_two(&parsed.0)?;
+    Ok(cut.components.0.len() * cut.components.1.len())
+}
+
+pub fn part1(input: &str) -> Result<usize, Error> {
+    solve_part1(&parse_input(input)?)
+}
+
+pub fn details(input: &str) -> Result<String, Error> {
+    let cut = cut_in_two(&parse(input)?)?;
+    let edges: Vec<String> = cut
+        .edges
+        .iter()
+        .map(|(a, b)| format!("{} -- {}", a, b))
+        .collect();
+    Ok(format!(
+        "cut: {}, component sizes: {}, {}",
+        edges.join(", "),
+        cut.components.0.len(),
+        cut.components.1.len()
+    ))
+}
+
+pub fn dot(input: &str) -> Result<String, Error> {
+    let graph = parse(input)?;
+    let cut = cut_in_two(&graph)?;
+    Ok(to_dot(&graph, &cut))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = include_str!("test_input");
+
+    #[test]
+    fn cut_edges() {
+        let cut = min_cut(&parse(TEST_INPUT).unwrap()).unwrap();
+        let edges: Vec<(&str, &str)> = cut
+            .edges
+            .iter()
+            .map(|(a, b)| (a.as_str(), b.as_str()))
+            .collect();
+        assert_eq!(edges, vec![("bvb", "cmg"), ("hfx", "pzl"), ("jqt", "nvd")]);
+    }
+
+    #[test]
+    fn graphviz() {
+        let document = dot(TEST_INPUT).unwrap();
+        let lines: Vec<&str> = document.lines().collect();
+
+        let cut: Vec<&str> = lines
             .iter()
-            .map(|&node| (node, outward_edges(node, &group, &graph)))
-            .max_by_key(|(_, n)| *n)
-            .unwrap()
-            .0
-            .clone();
-        group.remove(&node);
+            .filter(|line| line.ends_with("[color=red, penwidth=3];"))
+            .copied()
+            .collect();
+        assert_eq!(
+            cut,
+            [
+                "\t\"bvb\" -- \"cmg\" [color=red, penwidth=3];",
+                "\t\"hfx\" -- \"pzl\" [color=red, penwidth=3];",
+                "\t\"jqt\" -- \"nvd\" [color=red, penwidth=3];",
+            ]
+        );
+
+        let filled = |color: &str| {
+            lines
+                .iter()
+                .filter(|line| line.ends_with(&format!("[fillcolor={}];", color)))
+                .count()
+        };
+        let mut sizes = [filled("lightblue"), filled("palegreen")];
+        sizes.sort();
+        assert_eq!(sizes, [6, 9]);
+
+        assert_eq!(
+            dot("a-b: c\"d\n").unwrap(),
+            "graph day25 {\n\tnode [style=filled];\n\
+             \t\"a-b\" [fillcolor=lightblue];\n\
+             \t\"c\\\"d\" [fillcolor=palegreen];\n\
+             \t\"a-b\" -- \"c\\\"d\" [color=red, penwidth=3];\n}\n"
+        );
+    }
+
+    #[test]
+    fn part1_test_input() {
+        assert_eq!(part1(TEST_INPUT), Ok(54));
     }
 
-    println!("part 1: {}", group.len() * (graph.len() - group.len()))
+    #[test]
+    fn single_component() {
+        let graph = HashMap::from([("a".to_string(), HashSet::new())]);
+        assert_eq!(
+            solve_part1(&Parsed(graph)),
+            Err(Error::no_solution(
+                "the diagram has fewer than two components"
+            ))
+        );
+    }
 }